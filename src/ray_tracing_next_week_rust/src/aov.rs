@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::vec3::Vec3;
+use crate::color::{self, WorkingSpace};
+
+// Arbitrary output variables: first-hit passes written alongside the beauty image.
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum Aov {
+    Normal,    // Shading normal, in world space
+    Depth,     // Distance from the camera along its view direction
+    Albedo,    // Reflectance of the material, from its texture
+    Position,  // World space position
+    Uv,        // Surface texture coordinates, in the red and green channels
+    MaterialId // Material number, counting from 1; 0 where nothing was hit
+}
+
+// The first hit of a camera ray, or a sum or average of them over a pixel's samples. Misses are
+// all zero.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AovSample {
+    pub normal: Vec3,
+    pub depth: f64,
+    pub albedo: Vec3,
+    pub position: Vec3,
+    pub uv: Vec3,
+    pub material: Option<usize> // Identifies the material; not summed, so kept from the first hit
+}
+
+impl Aov {
+    pub fn name(&self) -> String {
+        return self.to_possible_value().map(|value| value.get_name().to_string()).unwrap_or_default();
+    }
+
+    pub fn path(&self, beauty_path: &str) -> String {
+        // Inserts the pass name before the extension, e.g. render.normal.exr for render.exr.
+        let path = Path::new(beauty_path);
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+        let mut file_name = format!("{}.{}", stem, self.name());
+        if let Some(extension) = path.extension() {
+            file_name = format!("{}.{}", file_name, extension.to_string_lossy());
+        }
+        return path.with_file_name(file_name).to_string_lossy().to_string();
+    }
+
+    pub fn image(&self, samples: &[AovSample], is_hdr: bool, space: WorkingSpace) -> Vec<Vec3> {
+        // High dynamic range formats get the raw values. Otherwise they're remapped to [0, 1] for
+        // viewing: normals from [-1, 1], depth and position relative to their extent in the image,
+        // albedo sRGB encoded and materials as arbitrary distinct colors.
+        let gray = |value: f64| Vec3::new(value, value, value);
+        match self {
+            Aov::Normal if is_hdr => samples.iter().map(|sample| sample.normal).collect(),
+            Aov::Normal => samples.iter().map(|sample| 0.5 * sample.normal + Vec3::new(0.5, 0.5, 0.5)).collect(),
+            Aov::Depth if is_hdr => samples.iter().map(|sample| gray(sample.depth)).collect(),
+            Aov::Depth => {
+                let max_depth = samples.iter().fold(0.0, |max_depth: f64, sample| max_depth.max(sample.depth));
+                let scale = if max_depth > 0.0 { 1.0 / max_depth } else { 0.0 };
+                samples.iter().map(|sample| gray(scale * sample.depth)).collect()
+            }
+            Aov::Albedo if is_hdr => samples.iter().map(|sample| sample.albedo).collect(),
+            Aov::Albedo => samples.iter().map(|sample| color::encode_srgb(space.working_to_rec709(sample.albedo))).collect(),
+            Aov::Position if is_hdr => samples.iter().map(|sample| sample.position).collect(),
+            Aov::Position => {
+                let hits: Vec<Vec3> = samples.iter().filter(|sample| sample.material.is_some()).map(|sample| sample.position).collect();
+                let min = hits.iter().fold(Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY), |a, b| Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)));
+                let max = hits.iter().fold(-min, |a, b| Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)));
+                let normalize = |value: f64, min: f64, max: f64| if max > min { (value - min) / (max - min) } else { 0.0 };
+                samples.iter().map(|sample| match sample.material {
+                    Some(_) => Vec3::new(
+                        normalize(sample.position.x, min.x, max.x),
+                        normalize(sample.position.y, min.y, max.y),
+                        normalize(sample.position.z, min.z, max.z)),
+                    None => Vec3::new(0.0, 0.0, 0.0)
+                }).collect()
+            }
+            Aov::Uv => samples.iter().map(|sample| sample.uv).collect(),
+            Aov::MaterialId if is_hdr => samples.iter().map(|sample| gray(material_number(sample) as f64)).collect(),
+            Aov::MaterialId => samples.iter().map(|sample| id_color(material_number(sample))).collect()
+        }
+    }
+}
+
+impl AovSample {
+    pub fn miss() -> Self {
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        Self {
+            normal: zero, depth: 0.0, albedo: zero, position: zero, uv: zero, material: None
+        }
+    }
+
+    pub fn accumulate(&self, sample: &AovSample) -> Self {
+        Self {
+            normal: self.normal + sample.normal,
+            depth: self.depth + sample.depth,
+            albedo: self.albedo + sample.albedo,
+            position: self.position + sample.position,
+            uv: self.uv + sample.uv,
+            material: self.material.or(sample.material)
+        }
+    }
+
+    pub fn scaled(&self, scale: f64) -> Self {
+        Self {
+            normal: scale * self.normal,
+            depth: scale * self.depth,
+            albedo: scale * self.albedo,
+            position: scale * self.position,
+            uv: scale * self.uv,
+            material: self.material
+        }
+    }
+}
+
+fn material_number(sample: &AovSample) -> usize {
+    return sample.material.map_or(0, |material| material + 1);
+}
+
+pub fn id_color(id: usize) -> Vec3 {
+    // Spreads consecutive ids around the hue circle with the golden ratio, leaving 0 black.
+    if id == 0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    let hue = (id as f64 * 0.618033988749895).fract() * 6.0;
+    let channel = |offset: f64| {
+        let distance = ((hue - offset).rem_euclid(6.0) - 3.0).abs();
+        (distance - 1.0).clamp(0.0, 1.0) * 0.75 + 0.25
+    };
+    return Vec3::new(channel(0.0), channel(2.0), channel(4.0));
+}
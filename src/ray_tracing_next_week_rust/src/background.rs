@@ -0,0 +1,49 @@
+use crate::vec3::Vec3;
+use crate::sampler::Sampler;
+
+// A direction towards the background picked by Background::sample.
+pub struct BackgroundSample {
+    pub direction: Vec3, // Unit length
+    pub color: Vec3,     // Radiance arriving from the direction
+    pub pdf: f64         // Probability density over solid angle of picking the direction
+}
+
+impl BackgroundSample {
+    pub fn new(direction: Vec3, color: Vec3, pdf: f64) -> Self {
+        Self {
+            direction, color, pdf
+        }
+    }
+}
+
+// Where a physical sky puts its sun, in degrees, and how hazy its air is.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SkySettings {
+    pub sun_elevation: f64, // Above the horizon
+    pub sun_azimuth: f64,   // Around the vertical axis from -z towards +x
+    pub turbidity: f64      // From 2 (very clear) to 10 (hazy)
+}
+
+// The light arriving from infinitely far away, seen by rays that leave the scene.
+pub trait Background : Send + Sync {
+    // Radiance from the unit direction, in the working space.
+    fn color(&self, direction: Vec3) -> Vec3;
+
+    // Picks a direction to light diffuse surfaces from, for backgrounds uneven enough that
+    // scattered rays alone are slow to find their light. The others return None without taking
+    // any sample dimensions.
+    fn sample(&self, _sampler: &mut dyn Sampler) -> Option<BackgroundSample> {
+        return None;
+    }
+
+    // Probability density over solid angle of sample picking the unit direction.
+    fn pdf(&self, _direction: Vec3) -> f64 {
+        return 0.0;
+    }
+
+    // The settings of a physical sky, so they can be adjusted from the command line; other
+    // backgrounds have none.
+    fn sky_settings(&self) -> Option<SkySettings> {
+        return None;
+    }
+}
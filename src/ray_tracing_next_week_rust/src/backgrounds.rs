@@ -0,0 +1,61 @@
+use clap::ValueEnum;
+
+use crate::background::Background;
+use crate::vec3::Vec3;
+use crate::color::WorkingSpace;
+use crate::physicalsky::PhysicalSky;
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum BackgroundKind {
+    Black,       // No light from outside the scene
+    Sky,         // A white to blue gradient sky
+    PhysicalSky  // A daylight sky and sun, placed by the sun's elevation and azimuth
+}
+
+impl BackgroundKind {
+    pub fn create(&self, space: WorkingSpace) -> Box<dyn Background> {
+        return match self {
+            BackgroundKind::Black => Box::new(SolidBackground::new(Vec3::new(0.0, 0.0, 0.0), space)),
+            BackgroundKind::Sky => Box::new(GradientSky::new(Vec3::new(1.0, 1.0, 1.0), Vec3::new(0.5, 0.7, 1.0), space)),
+            BackgroundKind::PhysicalSky => Box::new(PhysicalSky::new(45.0, 0.0, 3.0, space))
+        };
+    }
+}
+
+pub struct SolidBackground {
+    color: Vec3
+}
+
+impl SolidBackground {
+    pub fn new(color: Vec3, space: WorkingSpace) -> Self {
+        // The color is given with sRGB primaries, and kept in the scene's working space.
+        Self { color: space.rec709_to_working(color) }
+    }
+}
+
+impl Background for SolidBackground {
+    fn color(&self, _direction: Vec3) -> Vec3 {
+        return self.color;
+    }
+}
+
+// A vertical gradient, blending linearly from the bottom color straight down to the top color
+// straight up.
+pub struct GradientSky {
+    bottom: Vec3,
+    top: Vec3
+}
+
+impl GradientSky {
+    pub fn new(bottom: Vec3, top: Vec3, space: WorkingSpace) -> Self {
+        // The colors are given with sRGB primaries, and kept in the scene's working space.
+        Self { bottom: space.rec709_to_working(bottom), top: space.rec709_to_working(top) }
+    }
+}
+
+impl Background for GradientSky {
+    fn color(&self, direction: Vec3) -> Vec3 {
+        let a = 0.5 * (direction.y + 1.0);
+        return (1.0 - a) * self.bottom + a * self.top;
+    }
+}
@@ -1,8 +1,10 @@
 use crate::hittable::Hittable;
+use crate::material::Material;
 use crate::ray::Ray;
 use crate::interval::Interval;
 use crate::hitrecord::HitRecord;
 use crate::aabb::AABB;
+use crate::sampler::Sampler;
 use std::sync::Arc;
 
 pub struct BvhNode {
@@ -48,20 +50,18 @@ impl BvhNode {
         let left_objects = &objects[..mid];
         let right_objects = &objects[mid..];
 
-        let left: Arc<dyn Hittable>;
-        let right: Arc<dyn Hittable>;
-        if left_objects.len() == 1 {
-            left = Arc::clone(&left_objects[0]);
+        let left: Arc<dyn Hittable> = if left_objects.len() == 1 {
+            Arc::clone(&left_objects[0])
         }
         else {
-            left = Arc::new(BvhNode::new(left_objects));    
-        }
-        if right_objects.len() == 1 {
-            right = Arc::clone(&right_objects[0]);
+            Arc::new(BvhNode::new(left_objects))
+        };
+        let right: Arc<dyn Hittable> = if right_objects.len() == 1 {
+            Arc::clone(&right_objects[0])
         }
         else {
-            right = Arc::new(BvhNode::new(right_objects));
-        }
+            Arc::new(BvhNode::new(right_objects))
+        };
 
         return Self {
             left, right, bbox
@@ -70,17 +70,17 @@ impl BvhNode {
 }
 
 impl Hittable for BvhNode {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
         if !self.bbox.hit(ray, ray_t) {
             return None;
         }
 
-        let left_hit = self.left.hit(ray, ray_t);
+        let left_hit = self.left.hit(ray, ray_t, sampler);
 
         let right_t_max = if let Some(ref left_hit_r) = left_hit { left_hit_r.t } else { ray_t.max };
         let right_t = Interval::new(ray_t.min, right_t_max);
 
-        let right_hit = self.right.hit(ray, &right_t);
+        let right_hit = self.right.hit(ray, &right_t, sampler);
 
         if let Some (ref _right_hit_result) = right_hit {
             return right_hit;
@@ -95,4 +95,9 @@ impl Hittable for BvhNode {
     fn bounding_box(&self) -> AABB {
         return self.bbox;
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        self.left.collect_materials(materials);
+        self.right.collect_materials(materials);
+    }
 }
\ No newline at end of file
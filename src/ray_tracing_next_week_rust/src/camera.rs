@@ -1,61 +1,269 @@
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::Result;
 use std::cmp::max;
-use rayon::prelude::*;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::ray::Ray;
-use crate::hittable::Hittable;
-use crate::rtweekend::{degrees_to_radians, random_in_unit_disc};
+use crate::scene::Scene;
+use crate::rtweekend::{degrees_to_radians, sample_unit_disc};
 use crate::vec3;
 use crate::vec3::Vec3;
 use crate::interval::Interval;
 use crate::rtweekend;
+use crate::aov::AovSample;
+use crate::film::{Film, FilmTile};
+use crate::filter::{Filter, FilterKind};
+use crate::tiles::{self, Tile, TileOrder};
+use crate::progress::RenderCallback;
+use crate::crop::CropWindow;
+use crate::sampler::{Sampler, SamplerKind};
+use crate::integrator::{Integrator, Splat};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Camera {
     pub image_width: u64,     // Rendered image width in pixel count
 
+    aspect_ratio: f64,        // Ratio of image width over height
+    vfov: f64,                // Vertical view angle (field of view)
+    look_from: Vec3,
+    look_at: Vec3,
+    v_up: Vec3,
     samples_per_pixel: u64,
     image_height: u64,        // Rendered image height
     camera_center: Vec3,
+    view_direction: Vec3,     // Unit vector from the camera center towards look_at
     pixel00_loc: Vec3,        // Location of pixel 0, 0
     pixel_delta_u: Vec3,      // Offset of pixel to the right
     pixel_delta_v: Vec3,      // Offset to pixel below
-    pixel_samples_scale: f64, // Color scale factor for a sum of pixel samples
-    max_depth: u16,
+    tile_size: u64,           // Width and height of the square tiles the image is rendered in
+    tile_order: TileOrder,    // Order in which the tiles are rendered
+    crop_window: Option<CropWindow>, // Region to render, or all of the image
+    seed: u64,                // Seed of the random numbers of every sample
+    sampler: SamplerKind,     // Generator of the sample values
+    filter: Filter,           // Pixel reconstruction filter
+    adaptive_threshold: Option<f64>, // Relative error below which pixels stop being sampled
+    min_samples_per_pixel: u64, // Samples every pixel gets before its error is trusted
     defocus_angle: f64,
+    focus_dist: f64,          // Distance from look_from to the plane of perfect focus
     defocus_disc_u: Vec3, // Defocus disk horizontal radius
     defocus_disc_v: Vec3 // Defocus disk vertical radius
 }
 
+// A point on the lens that a point in the scene is seen from, for paths from the lights to reach the
+// camera through. The importance is the sensitivity of the pixel to light from the point, over the
+// density of picking the lens point, over solid angle at the point.
+pub struct LensSample {
+    pub p: Vec3,
+    pub x: f64, // Image position the point in the scene is seen at, with pixel centers at whole numbers
+    pub y: f64,
+    pub importance: f64
+}
+
+// Where the plane of perfect focus is, which with a defocus angle is the only plane in sharp focus.
+#[derive(Copy, Clone, Debug)]
+pub enum Focus {
+    Distance(f64), // This far from look_from along the view direction
+    LookAt         // Through look_at
+}
+
+// Settings of a camera, with defaults for everything a scene doesn't set.
+#[derive(Copy, Clone, Debug)]
+pub struct CameraBuilder {
+    aspect_ratio: f64,
+    image_width: u64,
+    vfov: f64,
+    look_from: Vec3,
+    look_at: Vec3,
+    v_up: Vec3,
+    defocus_angle: f64,
+    focus: Focus,
+    samples_per_pixel: u64,
+    tile_size: u64,
+    tile_order: TileOrder,
+    crop_window: Option<CropWindow>,
+    seed: u64,
+    sampler: SamplerKind,
+    filter: Filter,
+    adaptive_threshold: Option<f64>,
+    min_samples_per_pixel: u64
+}
+
+impl CameraBuilder {
+    pub fn new() -> Self {
+        Self {
+            aspect_ratio: 1.0,
+            image_width: 100,
+            vfov: 90.0,
+            look_from: Vec3::new(0.0, 0.0, 0.0),
+            look_at: Vec3::new(0.0, 0.0, -1.0),
+            v_up: Vec3::new(0.0, 1.0, 0.0),
+            defocus_angle: 0.0,
+            focus: Focus::LookAt,
+            samples_per_pixel: 100,
+            tile_size: 32,
+            tile_order: TileOrder::Hilbert,
+            crop_window: None,
+            seed: 0,
+            sampler: SamplerKind::Sobol,
+            filter: Filter::new(FilterKind::Box, 0.5),
+            adaptive_threshold: None,
+            min_samples_per_pixel: 16
+        }
+    }
+
+    pub fn aspect_ratio(mut self, aspect_ratio: f64) -> Self {
+        // Ratio of image width over height.
+        self.aspect_ratio = aspect_ratio;
+        return self;
+    }
+
+    pub fn image_width(mut self, image_width: u64) -> Self {
+        self.image_width = image_width;
+        return self;
+    }
+
+    pub fn vfov(mut self, vfov: f64) -> Self {
+        // Vertical view angle (field of view) in degrees.
+        self.vfov = vfov;
+        return self;
+    }
+
+    pub fn look_from(mut self, look_from: Vec3) -> Self {
+        self.look_from = look_from;
+        return self;
+    }
+
+    pub fn look_at(mut self, look_at: Vec3) -> Self {
+        self.look_at = look_at;
+        return self;
+    }
+
+    pub fn v_up(mut self, v_up: Vec3) -> Self {
+        self.v_up = v_up;
+        return self;
+    }
+
+    pub fn defocus_angle(mut self, defocus_angle: f64) -> Self {
+        // Variation angle of rays through each pixel in degrees; 0 keeps everything in focus.
+        self.defocus_angle = defocus_angle;
+        return self;
+    }
+
+    pub fn focus_distance(mut self, focus_distance: f64) -> Self {
+        self.focus = Focus::Distance(focus_distance);
+        return self;
+    }
+
+    pub fn focus_on_look_at(mut self) -> Self {
+        self.focus = Focus::LookAt;
+        return self;
+    }
+
+    pub fn samples_per_pixel(mut self, samples_per_pixel: u64) -> Self {
+        self.samples_per_pixel = samples_per_pixel;
+        return self;
+    }
+
+    pub fn tile_size(mut self, tile_size: u64) -> Self {
+        self.tile_size = tile_size;
+        return self;
+    }
+
+    pub fn tile_order(mut self, tile_order: TileOrder) -> Self {
+        self.tile_order = tile_order;
+        return self;
+    }
+
+    pub fn crop_window(mut self, crop_window: CropWindow) -> Self {
+        self.crop_window = Some(crop_window);
+        return self;
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        return self;
+    }
+
+    pub fn sampler(mut self, sampler: SamplerKind) -> Self {
+        self.sampler = sampler;
+        return self;
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        return self;
+    }
+
+    pub fn adaptive_sampling(mut self, threshold: f64, min_samples_per_pixel: u64) -> Self {
+        // Stops sampling pixels once they have min_samples_per_pixel samples and the estimated
+        // relative error of their mean is below threshold.
+        self.adaptive_threshold = Some(threshold);
+        self.min_samples_per_pixel = min_samples_per_pixel;
+        return self;
+    }
+
+    pub fn build(self) -> Camera {
+        let focus_dist = match self.focus {
+            Focus::Distance(distance) => distance,
+            Focus::LookAt => (self.look_from - self.look_at).length()
+        };
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        let mut camera = Camera {
+            image_width: self.image_width,
+            aspect_ratio: self.aspect_ratio,
+            vfov: self.vfov,
+            look_from: self.look_from,
+            look_at: self.look_at,
+            v_up: self.v_up,
+            samples_per_pixel: self.samples_per_pixel,
+            image_height: 1,
+            camera_center: zero,
+            view_direction: zero,
+            pixel00_loc: zero,
+            pixel_delta_u: zero,
+            pixel_delta_v: zero,
+            tile_size: self.tile_size,
+            tile_order: self.tile_order,
+            crop_window: self.crop_window,
+            seed: self.seed,
+            sampler: self.sampler,
+            filter: self.filter,
+            adaptive_threshold: self.adaptive_threshold,
+            min_samples_per_pixel: self.min_samples_per_pixel,
+            defocus_angle: self.defocus_angle,
+            focus_dist,
+            defocus_disc_u: zero,
+            defocus_disc_v: zero
+        };
+        camera.initialize();
+        return camera;
+    }
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Camera {
-    pub fn new
-    (
-        aspect_ratio: f64, // Ratio of image width over height
-        image_width: u64,  // Rendered image width in pixel count
-        vfov: f64,         // Vertical view angle (field of view)
-        look_from: Vec3,
-        look_at: Vec3,
-        v_up: Vec3,
-        defocus_angle: f64 // Variation angle of rays through each pixel
-    ) -> Self {
-        let image_height = max(1, (image_width as f64 / aspect_ratio) as u64);
-
-        let focus_dist = 10.0;  // Distance from camera lookfrom point to plane of perfect focus
+    fn initialize(&mut self) {
+        // Derive the image height and viewport geometry from the user-facing settings.
+        let image_height = max(1, (self.image_width as f64 / self.aspect_ratio) as u64);
+
+        let focus_dist = self.focus_dist;
 
         // Determine viewport dimensions.
-        let theta = rtweekend::degrees_to_radians(vfov);
+        let theta = rtweekend::degrees_to_radians(self.vfov);
         let h = f64::tan(theta / 2.0);
 
-        let camera_center = look_from;
+        let camera_center = self.look_from;
 
         let viewport_height = 2.0 * h * focus_dist;
-        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
+        let viewport_width = viewport_height * (self.image_width as f64 / image_height as f64);
 
         // Calculate the u,v,w unit basis vectors for the camera coordinate frame.
-        let w = (look_from - look_at).normalized();
-        let u = vec3::cross(&v_up, &w).normalized();
+        let w = (self.look_from - self.look_at).normalized();
+        let u = vec3::cross(&self.v_up, &w).normalized();
         let v = vec3::cross(&w, &u);
 
         // Calculate the vectors across the horizontal and down the vertical viewport edges.
@@ -63,7 +271,7 @@ impl Camera {
         let viewport_v = viewport_height * -v;  // Vector down viewport vertical edge
 
         // Calculate the horizontal and vertical delta vectors from pixel to pixel.
-        let pixel_delta_u = viewport_u / image_width as f64;
+        let pixel_delta_u = viewport_u / self.image_width as f64;
         let pixel_delta_v = viewport_v / image_height as f64;
 
         // Calculate the location of the upper left pixel.
@@ -72,149 +280,295 @@ impl Camera {
         - viewport_u / 2.0
         - viewport_v / 2.0;
 
-        let pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
+        let defocus_radius = focus_dist * f64::tan(degrees_to_radians(self.defocus_angle / 2.0));
 
-        let samples_per_pixel = 80; // Count of random samples for each pixel
-        let pixel_samples_scale = 1.0 / samples_per_pixel as f64;
+        self.image_height = image_height;
+        self.camera_center = camera_center;
+        self.view_direction = -w;
+        self.pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
+        self.pixel_delta_u = pixel_delta_u;
+        self.pixel_delta_v = pixel_delta_v;
+        self.defocus_disc_u = u * defocus_radius;
+        self.defocus_disc_v = v * defocus_radius;
+    }
 
-        let defocus_radius = focus_dist * f64::tan(degrees_to_radians(defocus_angle / 2.0));
-        let defocus_disc_u = u * defocus_radius;
-        let defocus_disc_v = v * defocus_radius;
+    pub fn image_height(&self) -> u64 {
+        return self.image_height;
+    }
 
-        Self {
-            samples_per_pixel: samples_per_pixel,
-            image_width: image_width,
-            image_height: image_height,
-            camera_center: camera_center,
-            pixel00_loc: pixel00_loc,
-            pixel_delta_u: pixel_delta_u,
-            pixel_delta_v: pixel_delta_v,
-            pixel_samples_scale : pixel_samples_scale,
-            max_depth: 2,  // Maximum number of ray bounces into scene
-            defocus_angle,
-            defocus_disc_u,
-            defocus_disc_v
-        }
+    pub fn samples_per_pixel(&self) -> u64 {
+        return self.samples_per_pixel;
     }
 
-    pub fn render (&self, world: &Box<dyn Hittable>, file_name: &str) {
-        let mut contents = String::new();
+    pub fn focus_distance(&self) -> f64 {
+        return self.focus_dist;
+    }
 
-        contents.push_str(&format!("P3\n{} {}\n255\n", self.image_width, self.image_height));
+    pub fn depth(&self, p: Vec3) -> f64 {
+        // Distance of the point from the camera along its view direction.
+        return vec3::dot(&(p - self.camera_center), &self.view_direction);
+    }
 
-        let mut rows =(0..self.image_height).into_par_iter().map(|row|
-            {
-                let mut row_pixels: Vec<Vec3> = Vec::new();
-                for col in 0 .. self.image_width {
-                    let mut pixel_color = Vec3::new(0.0, 0.0, 0.0);
+    pub fn sample_lens(&self, p: Vec3, sampler: &mut dyn Sampler) -> Option<LensSample> {
+        // Picks a point on the lens like camera rays do, and finds where in the render region p is
+        // seen through it, if anywhere. A pixel's sensitivity is normalized so that the light
+        // it gathers over its lens points and directions is its value.
+        let lens_point = self.lens_sample(sampler);
+        let (x, y, cos_theta) = self.pixel_towards(lens_point, p - lens_point)?;
+        let pixel_area = self.pixel_delta_u.length() * self.pixel_delta_v.length();
+        let distance_squared = (p - lens_point).length_squared();
+        let importance = self.focus_dist * self.focus_dist / (pixel_area * cos_theta.powi(3) * distance_squared);
+        return Some(LensSample { p: lens_point, x, y, importance });
+    }
 
-                    for _sample in 0 .. self.samples_per_pixel {
-                        let ray_r = Self::get_ray(&self, col as f64, row as f64);
-                        pixel_color = pixel_color + Self::ray_color(&ray_r, self.max_depth, &world);
-                    }
+    pub fn direction_pdf(&self, origin: Vec3, direction: Vec3) -> f64 {
+        // Probability density over solid angle of a camera ray from the lens point origin taking
+        // the direction, counting the pixel as picked uniformly from the render region; 0 if it
+        // misses the region.
+        let Some((_, _, cos_theta)) = self.pixel_towards(origin, direction) else {
+            return 0.0;
+        };
+        let pixel_area = self.pixel_delta_u.length() * self.pixel_delta_v.length();
+        let pixel_count = self.render_region().pixel_count() as f64;
+        return self.focus_dist * self.focus_dist / (pixel_area * cos_theta.powi(3) * pixel_count);
+    }
 
-                    row_pixels.push(pixel_color);
-                }
-                return (row, row_pixels);
-            }).collect::<Vec<(u64, Vec<Vec3>)>>();
+    pub fn new_film(&self, gather_aovs: bool) -> Film {
+        // An empty film matching the image resolution, for the sampler to spread the samples per
+        // pixel over.
+        return Film::new(self.image_width, self.image_height, gather_aovs, self.samples_per_pixel);
+    }
+
+    pub fn render_region(&self) -> Tile {
+        // The pixels that are traced: the crop window if there is one, or else the whole image.
+        return match self.crop_window {
+            Some(crop_window) => crop_window.region(self.image_width, self.image_height),
+            None => Tile { x0: 0, y0: 0, x1: self.image_width, y1: self.image_height }
+        };
+    }
 
-        rows.sort_by(|a, b| {
-            return a.0.partial_cmp(&b.0).unwrap();
+    pub fn tile_count(&self) -> u64 {
+        return tiles::tile_count(&self.render_region(), self.tile_size);
+    }
+
+    pub fn samples_wanted(&self, film: &Film, x: u64, y: u64) -> u64 {
+        // The number of samples the pixel still needs: none once it has converged, with adaptive
+        // sampling, or else up to samples_per_pixel.
+        let sample_count = film.sample_count(x, y);
+        if let Some(threshold) = self.adaptive_threshold
+            && sample_count >= self.min_samples_per_pixel
+            && film.relative_error(x, y) <= threshold {
+            return 0;
+        }
+        return self.samples_per_pixel.saturating_sub(sample_count);
+    }
+
+    pub fn render_done(&self, film: &Film) -> bool {
+        return self.render_region().pixels().all(|(x, y)| self.samples_wanted(film, x, y) == 0);
+    }
+
+    pub fn render (&self, scene: &Scene, integrator: &dyn Integrator, film: &mut Film, samples: u64, callback: Option<&dyn RenderCallback>) {
+        // Traces up to samples more samples through every pixel of the render region that wants
+        // them with the integrator, accumulating them in the film. The first-hit output variables
+        // are gathered if the film holds them.
+        //
+        // Each worker thread takes the next tile from the list until none are left. Samples are
+        // splatted into the pixels around them, so neighbouring tiles add to the same pixels; the
+        // finished tiles are added to the shared film in list order, so every pixel sums its
+        // samples in the same order whatever the number of threads. The callback is told about
+        // each tile as it is added.
+        let gather_aovs = film.has_aovs();
+        let sampler_samples = film.sampler_samples();
+        let tiles = tiles::tiles(&self.render_region(), self.tile_size, self.tile_order);
+        let next_tile = AtomicUsize::new(0);
+        let target = Mutex::new(PassTarget { film, finished: (0..tiles.len()).map(|_| None).collect(), next_to_add: 0 });
+
+        rayon::scope(|scope| {
+            for _worker in 0..rayon::current_num_threads() {
+                scope.spawn(|_| {
+                    loop {
+                        let tile_index = next_tile.fetch_add(1, Ordering::Relaxed);
+                        let Some(tile) = tiles.get(tile_index) else {
+                            break;
+                        };
+                        // Samples are numbered on from those already in the film, so that each
+                        // gets its own random numbers however the render is split into passes.
+                        let pixel_samples: Vec<(u64, u64)> = {
+                            let target = target.lock().unwrap();
+                            tile.pixels().map(|(x, y)| (target.film.sample_count(x, y), self.samples_wanted(target.film, x, y).min(samples))).collect()
+                        };
+                        let finished_tile = self.render_tile(scene, integrator, tile, &pixel_samples, sampler_samples, gather_aovs);
+
+                        let mut target = target.lock().unwrap();
+                        let target = &mut *target;
+                        target.finished[tile_index] = Some(finished_tile);
+                        while let Some((film_tile, tile_aovs)) = target.finished.get_mut(target.next_to_add).and_then(Option::take) {
+                            let tile = &tiles[target.next_to_add];
+                            target.film.add_tile(&film_tile);
+                            if gather_aovs {
+                                for (index, (x, y)) in tile.pixels().enumerate() {
+                                    target.film.add_aov_samples(x, y, &tile_aovs[index]);
+                                }
+                            }
+                            if let Some(callback) = callback {
+                                callback.tile_done(tile, film_tile.sample_count(), target.film);
+                            }
+                            target.next_to_add += 1;
+                        }
+                    }
+                });
+            }
         });
 
-        for row in rows.into_iter() {
-            for pixel_color in row.1.into_iter() {
-                write_color(&mut contents, &(self.pixel_samples_scale * pixel_color));
+        let PassTarget { film, .. } = target.into_inner().unwrap();
+        film.finish_pass();
+    }
+
+    fn splat_bounds(&self, tile: &Tile) -> Tile {
+        // The pixels of the render region within the filter radius of the tile's samples.
+        let region = self.render_region();
+        let margin = (self.filter.radius() - 0.5).ceil().max(0.0) as u64;
+        return Tile {
+            x0: tile.x0.saturating_sub(margin).max(region.x0),
+            y0: tile.y0.saturating_sub(margin).max(region.y0),
+            x1: (tile.x1 + margin).min(region.x1),
+            y1: (tile.y1 + margin).min(region.y1)
+        };
+    }
+
+    fn render_tile(&self, scene: &Scene, integrator: &dyn Integrator, tile: &Tile, pixel_samples: &[(u64, u64)], sampler_samples: u64, gather_aovs: bool) -> (FilmTile, Vec<AovSample>) {
+        // Takes the given numbers of samples of the tile's pixels, row by row, numbered from the
+        // given first sample, from a sampler set up for sampler_samples per pixel. Returns them
+        // splatted over the bounds, and the summed first-hit values of each pixel.
+        let mut film_tile = FilmTile::new(*tile, self.splat_bounds(tile));
+        let mut tile_aovs: Vec<AovSample> = Vec::new();
+        let mut sampler = self.sampler.create(self.seed, sampler_samples);
+        let mut splats: Vec<Splat> = Vec::new();
+        for (index, (col, row)) in tile.pixels().enumerate() {
+            let mut pixel_aov = AovSample::miss();
+
+            let (first_sample, samples) = pixel_samples[index];
+            for sample in first_sample .. first_sample + samples {
+                sampler.start_pixel_sample(col, row, sample);
+                let offset = Self::sample_square(sampler.as_mut());
+                let (x, y) = (col as f64 + offset.x, row as f64 + offset.y);
+                let ray_r = Self::get_ray(self, x, y, sampler.as_mut());
+
+                if gather_aovs {
+                    // A copy of the sampler makes the same choices at the first hit as the integrator.
+                    pixel_aov = pixel_aov.accumulate(&self.first_hit(&ray_r, scene, sampler.clone_box().as_mut()));
+                }
+
+                let color = integrator.radiance(ray_r, scene, sampler.as_mut(), &mut splats);
+                film_tile.add_sample(index, x, y, color, &self.filter, scene.working_space);
+                for splat in splats.drain(..) {
+                    self.add_splat(&mut film_tile, &splat);
+                }
             }
+
+            tile_aovs.push(pixel_aov);
         }
+        return (film_tile, tile_aovs);
+    }
 
-        write_file(file_name, &contents).expect("Unable to write to file");
+    fn add_splat(&self, film_tile: &mut FilmTile, splat: &Splat) {
+        // Spreads the light a light path carried to an image position over the pixels of the
+        // render region within the filter radius, like a camera sample there. Each weight is over
+        // the filter's integral across the area camera samples are taken in, which is what the
+        // weights of the pixel's camera samples add up to per sample, so both estimate the same
+        // filtered value, at the edges of the region too.
+        let region = self.render_region();
+        let radius = self.filter.radius();
+        let x0 = ((splat.x - radius).ceil().max(region.x0 as f64)) as u64;
+        let y0 = ((splat.y - radius).ceil().max(region.y0 as f64)) as u64;
+        let x1 = ((splat.x + radius).floor() + 1.0).min(region.x1 as f64) as u64;
+        let y1 = ((splat.y + radius).floor() + 1.0).min(region.y1 as f64) as u64;
+        let column_weights: Vec<f64> = (x0..x1)
+            .map(|px| self.filter.evaluate_1d(px as f64 - splat.x) / self.filter.integral(region.x0 as f64 - 0.5 - px as f64, region.x1 as f64 - 0.5 - px as f64))
+            .collect();
+        for py in y0..y1 {
+            let row_weight = self.filter.evaluate_1d(py as f64 - splat.y) / self.filter.integral(region.y0 as f64 - 0.5 - py as f64, region.y1 as f64 - 0.5 - py as f64);
+            for px in x0..x1 {
+                film_tile.add_splat(px, py, (row_weight * column_weights[(px - x0) as usize]) * splat.radiance);
+            }
+        }
     }
 
-    fn get_ray(&self, i: f64, j: f64) -> Ray {
-        // Construct a camera ray originating from the defocus disk and directed at randomly sampled
-        // point around the pixel location i, j.
+    fn get_ray(&self, i: f64, j: f64, sampler: &mut dyn Sampler) -> Ray {
+        // Construct a camera ray originating from the defocus disk and directed at the image
+        // position i, j, in pixels, with pixel centers at whole numbers.
 
-        let offset = Self::sample_square();
         let pixel_sample = self.pixel00_loc
-                          + ((i + offset.x) * self.pixel_delta_u)
-                          + ((j + offset.y) * self.pixel_delta_v);
+                          + (i * self.pixel_delta_u)
+                          + (j * self.pixel_delta_v);
 
-        let ray_origin = if self.defocus_angle <= 0.0 {
-            self.camera_center
-        } else {
-            self.defocus_disc_sample()
-        };
+        let ray_origin = self.lens_sample(sampler);
         let ray_direction = pixel_sample - ray_origin;
-        let ray_time = rtweekend::random_double();
+        let ray_time = sampler.get_1d();
 
         return Ray::new2(ray_origin, ray_direction, ray_time);
     }
 
-    fn sample_square() -> Vec3 {
-        // Returns the vector to a random point in the [-.5,-.5]-[+.5,+.5] unit square.
-        return Vec3::new(rtweekend::random_double() - 0.5, rtweekend::random_double() - 0.5, 0.0);
-    }
-
-    fn defocus_disc_sample(&self) -> Vec3 {
-        // Returns a random point in the camera defocus disk.
-        let p = random_in_unit_disc();
-        return self.camera_center + (p.x * self.defocus_disc_u) + (p.y * self.defocus_disc_v);
+    fn lens_sample(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        // The point camera rays start from: the camera center, or a point on the defocus disk.
+        if self.defocus_angle <= 0.0 {
+            return self.camera_center;
+        }
+        return self.defocus_disc_sample(sampler);
     }
 
-    fn ray_color(ray: &Ray, depth: u16, world: &Box<dyn Hittable>) -> Vec3 {
-        // If we've exceeded the ray bounce limit, no more light is gathered.
-        if depth <= 0 {
-            return Vec3::new(0.0, 0.0, 0.0);
+    fn pixel_towards(&self, origin: Vec3, direction: Vec3) -> Option<(f64, f64, f64)> {
+        // The image position, in pixels, where a ray from the lens point origin in the direction
+        // crosses the focus plane, if it is in a pixel of the render region, with the cosine of the
+        // direction to the view direction.
+        let cos_theta = vec3::dot(&direction.normalized(), &self.view_direction);
+        if cos_theta <= 0.0 {
+            return None;
         }
-
-        // NOTE: start interval at 0.0001 to avoid shadow acne from intersecting with same object
-        let hit_record = world.hit(ray, &Interval::new(0.0001, std::f64::INFINITY));
-        if let Some(hit) = hit_record {
-            let color_from_emission = hit.material.emitted(hit.u, hit.v, hit.p);
-
-            let scatter_result = hit.material.scatter(ray, &hit);
-            if let Some(scattered) = scatter_result {
-                let attenuation = scattered.attenuation;
-                let color_from_scatter = attenuation * Camera::ray_color(&scattered.scattered, depth - 1, world);
-                return color_from_emission + color_from_scatter;
-            }
-            return color_from_emission;
+        let focus_point = origin + (self.focus_dist / vec3::dot(&direction, &self.view_direction)) * direction;
+        let offset = focus_point - self.pixel00_loc;
+        let x = vec3::dot(&offset, &self.pixel_delta_u) / self.pixel_delta_u.length_squared();
+        let y = vec3::dot(&offset, &self.pixel_delta_v) / self.pixel_delta_v.length_squared();
+        let region = self.render_region();
+        if x.round() < region.x0 as f64 || x.round() >= region.x1 as f64 || y.round() < region.y0 as f64 || y.round() >= region.y1 as f64 {
+            return None;
         }
-
-        // TODO: background for most scenes is = color(0.70, 0.80, 1.00);
-        return Vec3::new(0.0, 0.0, 0.0); // TODO: background
+        return Some((x, y, cos_theta));
     }
-}
 
-fn write_file(file_path: &str, contents: &str) -> Result<()> {
-    let mut file = File::create(file_path)?;
-    file.write_all(contents.as_bytes())
-}
+    fn sample_square(sampler: &mut dyn Sampler) -> Vec3 {
+        // Returns the vector to a sampled point in the [-.5,-.5]-[+.5,+.5] unit square.
+        let (u, v) = sampler.get_2d();
+        return Vec3::new(u - 0.5, v - 0.5, 0.0);
+    }
 
-fn linear_to_gamma(linear_component: f64) -> f64 {
-    if linear_component > 0.0 {
-        return linear_component.sqrt();
+    fn defocus_disc_sample(&self, sampler: &mut dyn Sampler) -> Vec3 {
+        // Returns a sampled point in the camera defocus disk.
+        let p = sample_unit_disc(sampler.get_2d());
+        return self.camera_center + (p.x * self.defocus_disc_u) + (p.y * self.defocus_disc_v);
     }
 
-    return 0.0;
+    fn first_hit(&self, ray: &Ray, scene: &Scene, sampler: &mut dyn Sampler) -> AovSample {
+        // Gathers the output variables at the first surface seen along a camera ray.
+        let hit_record = scene.world.hit(ray, &Interval::new(0.0001, f64::INFINITY), sampler);
+        if let Some(hit) = hit_record {
+            return AovSample {
+                normal: hit.normal,
+                depth: self.depth(hit.p),
+                albedo: hit.material.albedo(&hit),
+                position: hit.p,
+                uv: Vec3::new(hit.u, hit.v, 0.0),
+                material: scene.material_id(&hit.material)
+            };
+        }
+        return AovSample::miss();
+    }
 }
 
-fn write_color(contents: &mut String, pixel_color: &Vec3) {
-    let r = pixel_color.x;
-    let g = pixel_color.y;
-    let b = pixel_color.z;
-
-    // Apply a linear to gamma transform for gamma 2
-    let r_gamma= linear_to_gamma(r);
-    let g_gamma= linear_to_gamma(g);
-    let b_gamma= linear_to_gamma(b);
-
-    // Translate the [0, 1] component value to byte range [0, 255]
-    let intensity = Interval::new(0.0, 0.999);
-    let r_byte = (256.0 * intensity.clamp(r_gamma)) as u8;
-    let g_byte = (256.0 * intensity.clamp(g_gamma)) as u8;
-    let b_byte = (256.0 * intensity.clamp(b_gamma)) as u8;
-
-    contents.push_str(&format!("{} {} {}\n", r_byte, g_byte, b_byte));
-}
\ No newline at end of file
+// The film of a pass, with the finished tiles waiting until those before them in the list have
+// been added.
+struct PassTarget<'a> {
+    film: &'a mut Film,
+    finished: Vec<Option<(FilmTile, Vec<AovSample>)>>,
+    next_to_add: usize
+}
@@ -0,0 +1,105 @@
+use clap::ValueEnum;
+
+use crate::vec3::Vec3;
+
+// Colors are given in scenes and textures with sRGB (Rec. 709) primaries, and converted into the
+// working space for rendering. Output converts back to sRGB primaries for display.
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum WorkingSpace {
+    Rec709, // Linear sRGB
+    Acescg  // Linear ACES AP1 primaries, D60 white point
+}
+
+pub type Matrix3 = [[f64; 3]; 3];
+
+// Linear Rec. 709 to ACEScg, with Bradford adaptation from D65 to D60.
+const REC709_TO_ACESCG: Matrix3 = [
+    [0.6130973, 0.3395229, 0.0473793],
+    [0.0701942, 0.9163555, 0.0134524],
+    [0.0206156, 0.1095698, 0.8698151]
+];
+
+const ACESCG_TO_REC709: Matrix3 = [
+    [1.7050510, -0.6217921, -0.0832589],
+    [-0.1302564, 1.1408048, -0.0105483],
+    [-0.0240033, -0.1289690, 1.1529723]
+];
+
+// CIE XYZ to linear Rec. 709, D65 white point.
+const XYZ_TO_REC709: Matrix3 = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252]
+];
+
+impl WorkingSpace {
+    pub fn chromaticities(&self) -> [f64; 8] {
+        // CIE xy of the red, green and blue primaries, then of the white point.
+        match self {
+            WorkingSpace::Rec709 => [0.64, 0.33, 0.30, 0.60, 0.15, 0.06, 0.3127, 0.3290],
+            WorkingSpace::Acescg => [0.713, 0.293, 0.165, 0.830, 0.128, 0.044, 0.32168, 0.33767]
+        }
+    }
+
+    pub fn rec709_to_working(self, color: Vec3) -> Vec3 {
+        // Converts a linear color with sRGB primaries into the working space.
+        match self {
+            WorkingSpace::Rec709 => color,
+            WorkingSpace::Acescg => transform(&REC709_TO_ACESCG, color)
+        }
+    }
+
+    pub fn xyz_to_working(self, color: Vec3) -> Vec3 {
+        // Converts a CIE XYZ color into the working space.
+        return self.rec709_to_working(transform(&XYZ_TO_REC709, color));
+    }
+
+    pub fn working_to_rec709(self, color: Vec3) -> Vec3 {
+        // Converts a working space color to linear sRGB primaries.
+        match self {
+            WorkingSpace::Rec709 => color,
+            WorkingSpace::Acescg => transform(&ACESCG_TO_REC709, color)
+        }
+    }
+}
+
+pub fn srgb_to_linear(encoded: f64) -> f64 {
+    // The sRGB electro-optical transfer function (IEC 61966-2-1).
+    if encoded <= 0.04045 {
+        return encoded / 12.92;
+    }
+    return ((encoded + 0.055) / 1.055).powf(2.4);
+}
+
+pub fn linear_to_srgb(linear: f64) -> f64 {
+    // The inverse of srgb_to_linear, used to encode output for display.
+    if linear <= 0.0 {
+        return 0.0;
+    }
+    if linear <= 0.0031308 {
+        return 12.92 * linear;
+    }
+    return 1.055 * linear.powf(1.0 / 2.4) - 0.055;
+}
+
+pub fn encode_srgb(color: Vec3) -> Vec3 {
+    // Applies the sRGB transfer function to each channel of a linear sRGB color.
+    return Vec3::new(linear_to_srgb(color.x), linear_to_srgb(color.y), linear_to_srgb(color.z));
+}
+
+pub fn srgb_byte_table() -> [f64; 256] {
+    // Linear values of the 256 sRGB encoded byte values, for decoding 8-bit textures.
+    let mut table = [0.0; 256];
+    for (byte, value) in table.iter_mut().enumerate() {
+        *value = srgb_to_linear(byte as f64 / 255.0);
+    }
+    return table;
+}
+
+pub fn transform(m: &Matrix3, v: Vec3) -> Vec3 {
+    return Vec3::new(
+        m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+        m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+        m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z
+    );
+}
@@ -7,7 +7,7 @@ use crate::ray::Ray;
 use crate::hitrecord::HitRecord;
 use crate::interval::Interval;
 use crate::aabb::AABB;
-use crate::rtweekend::random_double;
+use crate::sampler::Sampler;
 
 pub struct ConstantMedium {
     boundary: Arc<dyn Hittable + Sync>,
@@ -26,10 +26,10 @@ impl ConstantMedium {
 }
 
 impl Hittable for ConstantMedium {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
-        let hit_result = &self.boundary.hit(ray, &Interval::universe());
+    fn hit(&self, ray: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let hit_result = &self.boundary.hit(ray, &Interval::universe(), sampler);
         if let Some(hit) = hit_result {
-            let hit_result2 = &self.boundary.hit(ray, &Interval::new(hit.t + 0.0001, f64::INFINITY));
+            let hit_result2 = &self.boundary.hit(ray, &Interval::new(hit.t + 0.0001, f64::INFINITY), sampler);
             if let Some(hit2) = hit_result2
             {
                 let mut hit_t1 = hit.t.max(ray_t.min);
@@ -44,7 +44,7 @@ impl Hittable for ConstantMedium {
 
                 let ray_length = ray.direction.length();
                 let distance_inside_boundary = (hit_t2 - hit_t1) * ray_length;
-                let rnd = random_double();
+                let rnd = sampler.get_1d();
                 let rnd_ln = rnd.ln();
                 let hit_distance = self.neg_inv_density * rnd_ln;
 
@@ -71,4 +71,9 @@ impl Hittable for ConstantMedium {
     fn bounding_box(&self) -> AABB {
         return self.boundary.bounding_box();
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        // Rays only ever hit the medium itself, never its boundary's surfaces.
+        materials.push(Arc::clone(&self.phase_function));
+    }
 }
\ No newline at end of file
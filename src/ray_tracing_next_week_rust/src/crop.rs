@@ -0,0 +1,62 @@
+use clap::ValueEnum;
+
+use crate::tiles::Tile;
+
+// A region of the image to render, as X0,Y0,X1,Y1 with the end corner excluded.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CropWindow {
+    Pixels([u64; 4]),    // Pixel coordinates from the top left
+    Normalized([f64; 4]) // Fractions of the image width and height, in [0, 1]
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum CropOutput {
+    Cropped, // Just the crop window
+    Full     // The full frame, with the crop window at its place
+}
+
+impl CropWindow {
+    pub fn region(&self, width: u64, height: u64) -> Tile {
+        // The pixels covered by the window, clipped to the image and at least one pixel in size.
+        let [x0, y0, x1, y1] = match self {
+            CropWindow::Pixels(corners) => *corners,
+            CropWindow::Normalized([x0, y0, x1, y1]) => [
+                (x0 * width as f64).floor() as u64,
+                (y0 * height as f64).floor() as u64,
+                (x1 * width as f64).ceil() as u64,
+                (y1 * height as f64).ceil() as u64
+            ]
+        };
+        let x0 = x0.min(width - 1);
+        let y0 = y0.min(height - 1);
+        return Tile { x0, y0, x1: x1.clamp(x0 + 1, width), y1: y1.clamp(y0 + 1, height) };
+    }
+}
+
+pub fn parse_pixels(text: &str) -> Result<CropWindow, String> {
+    let [x0, y0, x1, y1] = parse_corners(text)?;
+    let pixel = |value: f64| if value >= 0.0 && value.fract() == 0.0 { Ok(value as u64) } else { Err(format!("'{}' is not a pixel coordinate", value)) };
+    let corners = [pixel(x0)?, pixel(y0)?, pixel(x1)?, pixel(y1)?];
+    if corners[2] <= corners[0] || corners[3] <= corners[1] {
+        return Err("the window is empty; X1 and Y1 must be greater than X0 and Y0".to_string());
+    }
+    return Ok(CropWindow::Pixels(corners));
+}
+
+pub fn parse_normalized(text: &str) -> Result<CropWindow, String> {
+    let corners = parse_corners(text)?;
+    if corners.iter().any(|value| !(0.0..=1.0).contains(value)) {
+        return Err("normalized coordinates must be in [0, 1]".to_string());
+    }
+    if corners[2] <= corners[0] || corners[3] <= corners[1] {
+        return Err("the window is empty; X1 and Y1 must be greater than X0 and Y0".to_string());
+    }
+    return Ok(CropWindow::Normalized(corners));
+}
+
+fn parse_corners(text: &str) -> Result<[f64; 4], String> {
+    let values: Vec<f64> = text.split(',')
+        .map(|value| value.trim().parse::<f64>().map_err(|_| format!("'{}' is not a number", value.trim())))
+        .collect::<Result<_, _>>()?;
+    return values.try_into().map_err(|_| "expected four values, X0,Y0,X1,Y1".to_string());
+}
@@ -0,0 +1,156 @@
+// A piecewise-constant distribution over [0, 1): the function values divide the interval into
+// equal segments, and points are picked with a density proportional to the value of their segment.
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,  // Cumulative probability at the start of each segment, and 1 at the end
+    integral: f64
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f64>) -> Self {
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i].abs() / n as f64;
+        }
+        let integral = cdf[n];
+        for (i, value) in cdf.iter_mut().enumerate() {
+            // A function that is zero everywhere is sampled uniformly.
+            *value = if integral > 0.0 { *value / integral } else { i as f64 / n as f64 };
+        }
+        Self {
+            func, cdf, integral
+        }
+    }
+
+    pub fn integral(&self) -> f64 {
+        return self.integral;
+    }
+
+    pub fn sample_continuous(&self, u: f64) -> (f64, f64, usize) {
+        // Returns the point for the uniform sample u, its density and the segment it is in.
+        let n = self.func.len();
+        let offset = self.cdf.partition_point(|&value| value <= u).saturating_sub(1).min(n - 1);
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 { (u - self.cdf[offset]) / width } else { 0.0 };
+        let x = ((offset as f64 + du) / n as f64).min(1.0 - f64::EPSILON / 2.0);
+        return (x, self.pdf(offset), offset);
+    }
+
+    pub fn pdf(&self, offset: usize) -> f64 {
+        // The density in the given segment.
+        return if self.integral > 0.0 { self.func[offset].abs() / self.integral } else { 1.0 };
+    }
+}
+
+// A piecewise-constant distribution over [0, 1)^2, from a width x height grid of values stored
+// row by row: a row is picked from the marginal distribution of the row integrals, then a point
+// in it from the row's own distribution.
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D
+}
+
+impl Distribution2D {
+    pub fn new(values: &[f64], width: usize) -> Self {
+        let conditional: Vec<Distribution1D> = values.chunks(width)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|row| row.integral()).collect());
+        Self {
+            conditional, marginal
+        }
+    }
+
+    pub fn sample_continuous(&self, u: (f64, f64)) -> ((f64, f64), f64) {
+        // Returns the point for the uniform sample u and its density.
+        let (y, row_pdf, row) = self.marginal.sample_continuous(u.1);
+        let (x, column_pdf, _) = self.conditional[row].sample_continuous(u.0);
+        return ((x, y), row_pdf * column_pdf);
+    }
+
+    pub fn pdf(&self, point: (f64, f64)) -> f64 {
+        let rows = self.conditional.len();
+        let row = ((point.1 * rows as f64) as usize).min(rows - 1);
+        let columns = self.conditional[row].func.len();
+        let column = ((point.0 * columns as f64) as usize).min(columns - 1);
+        return self.marginal.pdf(row) * self.conditional[row].pdf(column);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 4 x 3 grid with an empty row and an empty column.
+    const WIDTH: usize = 4;
+    const VALUES: [f64; 12] = [
+        1.0, 0.0, 3.0, 2.0,
+        0.0, 0.0, 0.0, 0.0,
+        5.0, 0.0, 0.5, 4.0
+    ];
+
+    fn stratified_samples(n: usize) -> impl Iterator<Item = (f64, f64)> {
+        // An n x n grid of samples, including both edges of every cell of the distribution.
+        return (0..n * n).map(move |i| ((i % n) as f64 / n as f64, (i / n) as f64 / n as f64));
+    }
+
+    fn cell(point: (f64, f64)) -> usize {
+        return (point.1 * 3.0) as usize * WIDTH + (point.0 * WIDTH as f64) as usize;
+    }
+
+    #[test]
+    fn sampled_density_matches_pdf() {
+        let distribution = Distribution2D::new(&VALUES, WIDTH);
+        for u in stratified_samples(96) {
+            let (point, pdf) = distribution.sample_continuous(u);
+            assert!((0.0..1.0).contains(&point.0) && (0.0..1.0).contains(&point.1));
+            assert!(pdf > 0.0);
+            assert!((pdf - distribution.pdf(point)).abs() < 1e-12 * pdf, "{:?}: {} against {}", u, pdf, distribution.pdf(point));
+        }
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_and_follows_the_values() {
+        let distribution = Distribution2D::new(&VALUES, WIDTH);
+        let total: f64 = VALUES.iter().sum();
+        let cell_area = 1.0 / VALUES.len() as f64;
+        let mut integral = 0.0;
+        for (index, value) in VALUES.iter().enumerate() {
+            let center = (((index % WIDTH) as f64 + 0.5) / WIDTH as f64, ((index / WIDTH) as f64 + 0.5) / 3.0);
+            let pdf = distribution.pdf(center);
+            assert!((pdf * cell_area - value / total).abs() < 1e-12);
+            integral += pdf * cell_area;
+        }
+        assert!((integral - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn empty_rows_and_columns_are_never_picked() {
+        let distribution = Distribution2D::new(&VALUES, WIDTH);
+        let mut counts = [0; 12];
+        let n = 240;
+        for u in stratified_samples(n).chain([(1.0 - f64::EPSILON / 2.0, 1.0 - f64::EPSILON / 2.0)]) {
+            let (point, _pdf) = distribution.sample_continuous(u);
+            counts[cell(point)] += 1;
+        }
+        let total: f64 = VALUES.iter().sum();
+        for (index, value) in VALUES.iter().enumerate() {
+            if *value == 0.0 {
+                assert_eq!(counts[index], 0, "cell {} was picked", index);
+            } else {
+                let fraction = counts[index] as f64 / (n * n) as f64;
+                assert!((fraction - value / total).abs() < 0.01, "cell {}: {} against {}", index, fraction, value / total);
+            }
+        }
+    }
+
+    #[test]
+    fn all_zero_values_are_sampled_uniformly() {
+        let distribution = Distribution1D::new(vec![0.0; 4]);
+        assert_eq!(distribution.integral(), 0.0);
+        let (x, pdf, offset) = distribution.sample_continuous(0.6);
+        assert!((x - 0.6).abs() < 1e-12);
+        assert_eq!((pdf, offset), (1.0, 2));
+    }
+}
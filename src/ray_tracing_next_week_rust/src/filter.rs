@@ -0,0 +1,111 @@
+use std::f64::consts::PI;
+
+use clap::ValueEnum;
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum FilterKind {
+    Box,           // Equal weights out to the radius; just the pixel's own samples at radius 0.5
+    Tent,          // Weights falling linearly to zero at the radius
+    Gaussian,      // A Gaussian with a standard deviation of a third of the radius
+    Mitchell,      // Mitchell-Netravali cubic with B = C = 1/3; sharp, with slightly negative lobes
+    Lanczos,       // Sinc windowed by a wider sinc; sharpest, with some ringing
+    BlackmanHarris // Blackman-Harris window; smooth, close to a Gaussian with a tighter tail
+}
+
+impl FilterKind {
+    pub fn default_radius(&self) -> f64 {
+        return match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 3.0,
+            FilterKind::BlackmanHarris => 1.5
+        };
+    }
+}
+
+// A pixel reconstruction filter: each sample is added to every pixel whose center is within the
+// radius, weighted by the filter at the offset from the pixel center to the sample, and each
+// pixel is the weighted average of the samples it gets. Radii are in pixels.
+#[derive(Copy, Clone, Debug)]
+pub struct Filter {
+    kind: FilterKind,
+    radius: f64,
+    integral: f64  // Of evaluate_1d over the whole radius
+}
+
+// Steps of the midpoint rule the filter is integrated with.
+const INTEGRATION_STEPS: u32 = 256;
+
+impl Filter {
+    pub fn new(kind: FilterKind, radius: f64) -> Self {
+        let mut filter = Self {
+            kind, radius, integral: 0.0
+        };
+        filter.integral = filter.integrate(-radius, radius);
+        return filter;
+    }
+
+    pub fn radius(&self) -> f64 {
+        return self.radius;
+    }
+
+    pub fn evaluate_1d(&self, x: f64) -> f64 {
+        // The filters are separable: the weight at offset dx, dy is evaluate_1d(dx) * evaluate_1d(dy).
+        let x = x.abs();
+        let r = self.radius;
+        if x > r {
+            return 0.0;
+        }
+        return match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Tent => r - x,
+            FilterKind::Gaussian => {
+                // Less its value at the radius, so that it falls to zero there.
+                let sigma = r / 3.0;
+                let gaussian = |x: f64| (-x * x / (2.0 * sigma * sigma)).exp();
+                gaussian(x) - gaussian(r)
+            },
+            FilterKind::Mitchell => mitchell(2.0 * x / r),
+            FilterKind::Lanczos => sinc(x) * sinc(x / r),
+            FilterKind::BlackmanHarris => {
+                let t = PI * x / r;
+                0.35875 + 0.48829 * t.cos() + 0.14128 * (2.0 * t).cos() + 0.01168 * (3.0 * t).cos()
+            }
+        };
+    }
+
+    pub fn integral(&self, a: f64, b: f64) -> f64 {
+        // The integral of evaluate_1d over [a, b]; the whole filter's is kept, as most spans cover it.
+        if a <= -self.radius && b >= self.radius {
+            return self.integral;
+        }
+        return self.integrate(a.max(-self.radius), b.min(self.radius));
+    }
+
+    fn integrate(&self, a: f64, b: f64) -> f64 {
+        if b <= a {
+            return 0.0;
+        }
+        let step = (b - a) / INTEGRATION_STEPS as f64;
+        let sum: f64 = (0..INTEGRATION_STEPS).map(|i| self.evaluate_1d(a + (i as f64 + 0.5) * step)).sum();
+        return sum * step;
+    }
+}
+
+fn mitchell(x: f64) -> f64 {
+    // The Mitchell-Netravali cubic over [0, 2].
+    let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+    if x < 1.0 {
+        return ((12.0 - 9.0 * b - 6.0 * c) * x * x * x + (-18.0 + 12.0 * b + 6.0 * c) * x * x + (6.0 - 2.0 * b)) / 6.0;
+    }
+    return ((-b - 6.0 * c) * x * x * x + (6.0 * b + 30.0 * c) * x * x + (-12.0 * b - 48.0 * c) * x + (8.0 * b + 24.0 * c)) / 6.0;
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        return 1.0;
+    }
+    return (PI * x).sin() / (PI * x);
+}
@@ -1,10 +1,13 @@
 use std::sync::Arc;
 
-use crate::hittable::Hittable;
+use crate::hittable::{Hittable, SurfaceSample};
+use crate::material::Material;
 use crate::hitrecord::HitRecord;
 use crate::ray::Ray;
 use crate::interval::Interval;
 use crate::aabb::AABB;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
 
 pub struct Hittables {
     objects: Vec<Arc<dyn Hittable>>,
@@ -23,16 +26,20 @@ impl Hittables {
             objects, bbox
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        return self.objects.is_empty();
+    }
 }
 
 impl Hittable for Hittables {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
         let mut hit_record: Option<HitRecord> = Option::None;
 
         let mut closest_so_far = ray_t.max;
 
         for object in self.objects.iter() {
-            let object_hit_result = object.hit(ray, &Interval::new(ray_t.min, closest_so_far));
+            let object_hit_result = object.hit(ray, &Interval::new(ray_t.min, closest_so_far), sampler);
             if let Some(object_hit) = object_hit_result {
                 closest_so_far = object_hit.t;
                 hit_record = Some(object_hit);
@@ -45,4 +52,44 @@ impl Hittable for Hittables {
     fn bounding_box(&self) -> AABB {
         return self.bbox;
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        for object in self.objects.iter() {
+            object.collect_materials(materials);
+        }
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f64) -> f64 {
+        // random picks each object with equal probability.
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self.objects.iter().map(|object| object.pdf_value(origin, direction, time)).sum();
+        return sum / self.objects.len() as f64;
+    }
+
+    fn random(&self, origin: Vec3, time: f64, sampler: &mut dyn Sampler) -> Vec3 {
+        let count = self.objects.len();
+        let index = ((sampler.get_1d() * count as f64) as usize).min(count - 1);
+        return self.objects[index].random(origin, time, sampler);
+    }
+
+    fn sample_surface(&self, time: f64, sampler: &mut dyn Sampler) -> Option<SurfaceSample> {
+        // Each object is picked with equal probability, whatever its area.
+        if self.objects.is_empty() {
+            return None;
+        }
+        let count = self.objects.len();
+        let index = ((sampler.get_1d() * count as f64) as usize).min(count - 1);
+        let sample = self.objects[index].sample_surface(time, sampler)?;
+        return Some(SurfaceSample { pdf: sample.pdf / count as f64, ..sample });
+    }
+
+    fn surface_pdf(&self, p: Vec3, time: f64) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self.objects.iter().map(|object| object.surface_pdf(p, time)).sum();
+        return sum / self.objects.len() as f64;
+    }
 }
\ No newline at end of file
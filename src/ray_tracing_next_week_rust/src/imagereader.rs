@@ -0,0 +1,231 @@
+use std::fs;
+use std::io;
+
+use crate::vec3::Vec3;
+
+// Reads a Radiance RGBE (.hdr) or portable float map (.pfm) image. Returns its width, height and
+// linear pixels, stored row by row from the top left.
+pub fn read_hdr_image(path: &str) -> io::Result<(usize, usize, Vec<Vec3>)> {
+    let data = fs::read(path)?;
+    if data.starts_with(b"#?") {
+        return read_rgbe(&data);
+    }
+    if data.starts_with(b"PF") {
+        return read_pfm(&data);
+    }
+    return Err(invalid_data("not a Radiance RGBE or portable float map image"));
+}
+
+pub fn is_hdr_path(path: &str) -> bool {
+    let extension = path.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase());
+    return matches!(extension.as_deref(), Some("hdr") | Some("pfm"));
+}
+
+fn read_rgbe(data: &[u8]) -> io::Result<(usize, usize, Vec<Vec3>)> {
+    // The header is lines of text ending with an empty one, followed by the resolution line.
+    let mut position = 0;
+    loop {
+        let line = next_line(data, &mut position)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") && format != "32-bit_rle_rgbe" {
+            return Err(invalid_data(&format!("unsupported pixel format {}", format)));
+        }
+    }
+    let resolution = next_line(data, &mut position)?;
+    let fields: Vec<&str> = resolution.split_whitespace().collect();
+    let (height, width) = match fields[..] {
+        ["-Y", height, "+X", width] => (parse_size(height)?, parse_size(width)?),
+        _ => return Err(invalid_data(&format!("unsupported image orientation '{}'", resolution)))
+    };
+
+    // Every scanline takes at least its header and a full-length run per channel, or a flat
+    // pixel each when it can't be run-length encoded; a header claiming more than the file holds
+    // is rejected before anything is allocated.
+    let scanline_bytes = if (8..0x8000).contains(&width) { Some(4 + 8 * width.div_ceil(127)) } else { width.checked_mul(4) };
+    if width.checked_mul(height).is_none() || scanline_bytes.and_then(|bytes| bytes.checked_mul(height)).is_none_or(|bytes| bytes > data.len() - position) {
+        return Err(truncated());
+    }
+    let mut pixels = Vec::with_capacity(width * height);
+    let mut scanline = vec![[0u8; 4]; width];
+    for _ in 0..height {
+        let header = data.get(position..position + 4).ok_or_else(truncated)?;
+        let run_length_encoded = (8..0x8000).contains(&width)
+            && header[0] == 2 && header[1] == 2 && ((header[2] as usize) << 8 | header[3] as usize) == width;
+        if run_length_encoded {
+            // Each channel in turn, as runs of (128 + length, value) and literal spans of
+            // (length, values...).
+            position += 4;
+            for channel in 0..4 {
+                let mut x = 0;
+                while x < width {
+                    let count = *data.get(position).ok_or_else(truncated)? as usize;
+                    position += 1;
+                    if count > 128 {
+                        let length = count - 128;
+                        let value = *data.get(position).ok_or_else(truncated)?;
+                        position += 1;
+                        if x + length > width {
+                            return Err(invalid_data("run overflows the scanline"));
+                        }
+                        for rgbe in scanline[x..x + length].iter_mut() {
+                            rgbe[channel] = value;
+                        }
+                        x += length;
+                    } else {
+                        if count == 0 || x + count > width {
+                            return Err(invalid_data("bad literal span in the scanline"));
+                        }
+                        let values = data.get(position..position + count).ok_or_else(truncated)?;
+                        for (rgbe, value) in scanline[x..x + count].iter_mut().zip(values) {
+                            rgbe[channel] = *value;
+                        }
+                        position += count;
+                        x += count;
+                    }
+                }
+            }
+        } else {
+            let values = data.get(position..position + 4 * width).ok_or_else(truncated)?;
+            for (rgbe, value) in scanline.iter_mut().zip(values.chunks(4)) {
+                rgbe.copy_from_slice(value);
+            }
+            position += 4 * width;
+        }
+        pixels.extend(scanline.iter().map(from_rgbe));
+    }
+    return Ok((width, height, pixels));
+}
+
+fn read_pfm(data: &[u8]) -> io::Result<(usize, usize, Vec<Vec3>)> {
+    // "PF", the width, the height and the scale, separated by whitespace; a negative scale marks
+    // little endian data. A single whitespace character separates the header from the floats,
+    // which are stored from the bottom row up.
+    let mut position = 0;
+    let mut fields = Vec::new();
+    while fields.len() < 4 {
+        while data.get(position).ok_or_else(truncated)?.is_ascii_whitespace() {
+            position += 1;
+        }
+        let start = position;
+        while !data.get(position).ok_or_else(truncated)?.is_ascii_whitespace() {
+            position += 1;
+        }
+        fields.push(String::from_utf8_lossy(&data[start..position]).into_owned());
+    }
+    position += 1;
+
+    if fields[0] != "PF" {
+        return Err(invalid_data("only color portable float maps are supported"));
+    }
+    let width = parse_size(&fields[1])?;
+    let height = parse_size(&fields[2])?;
+    let scale: f64 = fields[3].parse().map_err(|_| invalid_data("bad scale"))?;
+
+    let floats = width.checked_mul(height).and_then(|count| count.checked_mul(12))
+        .and_then(|length| data.get(position..)?.get(..length))
+        .ok_or_else(truncated)?;
+    let read_float = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        return if scale < 0.0 { f32::from_le_bytes(bytes) } else { f32::from_be_bytes(bytes) } as f64;
+    };
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in floats.chunks(12 * width).rev() {
+        pixels.extend(row.chunks(12).map(|pixel| Vec3::new(read_float(&pixel[0..4]), read_float(&pixel[4..8]), read_float(&pixel[8..12]))));
+    }
+    return Ok((width, height, pixels));
+}
+
+fn from_rgbe(rgbe: &[u8; 4]) -> Vec3 {
+    // The mantissas share the exponent; each is taken from the middle of its rounding interval.
+    if rgbe[3] == 0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    let scale = 2.0_f64.powi(rgbe[3] as i32 - 136);
+    return Vec3::new((rgbe[0] as f64 + 0.5) * scale, (rgbe[1] as f64 + 0.5) * scale, (rgbe[2] as f64 + 0.5) * scale);
+}
+
+fn next_line(data: &[u8], position: &mut usize) -> io::Result<String> {
+    let rest = data.get(*position..).ok_or_else(truncated)?;
+    let length = rest.iter().position(|byte| *byte == b'\n').ok_or_else(truncated)?;
+    *position += length + 1;
+    return Ok(String::from_utf8_lossy(&rest[..length]).trim_end().to_string());
+}
+
+fn parse_size(field: &str) -> io::Result<usize> {
+    return match field.parse::<usize>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(invalid_data(&format!("bad image size '{}'", field)))
+    };
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, message);
+}
+
+fn truncated() -> io::Error {
+    return io::Error::new(io::ErrorKind::UnexpectedEof, "the image is truncated");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WorkingSpace;
+    use crate::imagewriter::{HdrWriter, ImageWriter, PfmWriter};
+
+    fn encode(writer: &dyn ImageWriter, width: u64, height: u64, pixels: &[Vec3]) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        writer.write(&mut out, width, height, pixels, WorkingSpace::Rec709).unwrap();
+        return out;
+    }
+
+    fn gradient(width: usize, height: usize) -> Vec<Vec3> {
+        return (0..width * height).map(|index| Vec3::new((index % width) as f64 + 0.25, (index / width) as f64 * 0.5, 3.0)).collect();
+    }
+
+    #[test]
+    fn pfm_round_trip() {
+        let pixels = gradient(5, 3);
+        let (width, height, read) = read_pfm(&encode(&PfmWriter, 5, 3, &pixels)).unwrap();
+        assert_eq!((width, height), (5, 3));
+        assert_eq!(read, pixels);
+    }
+
+    #[test]
+    fn rgbe_round_trip() {
+        // Flat and run-length encoded scanlines, to within RGBE's 8-bit mantissas.
+        for width in [3, 20] {
+            let pixels = gradient(width, 4);
+            let (read_width, read_height, read) = read_rgbe(&encode(&HdrWriter, width as u64, 4, &pixels)).unwrap();
+            assert_eq!((read_width, read_height), (width, 4));
+            for (pixel, expected) in read.iter().zip(pixels.iter()) {
+                let brightest = expected.x.max(expected.y).max(expected.z);
+                assert!((*pixel - *expected).length() <= brightest / 128.0, "{:?} against {:?}", pixel, expected);
+            }
+        }
+    }
+
+    fn assert_truncated(result: io::Result<(usize, usize, Vec<Vec3>)>) {
+        let error = result.expect_err("a malformed image was read");
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "{}", error);
+    }
+
+    #[test]
+    fn sizes_beyond_the_data_are_rejected() {
+        // Huge sizes whose byte counts overflow, and sizes just past the data.
+        let max = usize::MAX;
+        assert_truncated(read_pfm(format!("PF\n{} {}\n-1.0\n", max, max).as_bytes()));
+        assert_truncated(read_pfm(format!("PF\n{} 2\n-1.0\n", max / 8).as_bytes()));
+        let mut pfm = encode(&PfmWriter, 5, 3, &gradient(5, 3));
+        pfm.pop();
+        assert_truncated(read_pfm(&pfm));
+
+        assert_truncated(read_rgbe(format!("#?RADIANCE\n\n-Y {} +X {}\n", max, max).as_bytes()));
+        assert_truncated(read_rgbe(format!("#?RADIANCE\n\n-Y {} +X 20\n", max / 16).as_bytes()));
+        assert_truncated(read_rgbe(format!("#?RADIANCE\n\n-Y 1 +X {}\n", max / 2).as_bytes()));
+        let mut hdr = encode(&HdrWriter, 20, 4, &gradient(20, 4));
+        hdr.pop();
+        assert_truncated(read_rgbe(&hdr));
+    }
+}
@@ -1,42 +1,70 @@
-use load_image::export::rgb::Rgba;
-use std::str;
 use crate::texture::Texture;
 use crate::vec3::Vec3;
 use crate::interval::Interval;
+use crate::color::{self, WorkingSpace};
+use crate::imagereader;
 
 pub struct ImageTexture {
-    image: Vec<Rgba<u8>>,
+    texels: Vec<Vec3>, // Linear, in the working color space
     width: usize,
     height: usize
 }
 
 impl ImageTexture {
-    pub fn new(path: &str) -> Self {
-        let img = load_image::load_path(path).unwrap();
+    pub fn new(path: &str, space: WorkingSpace) -> Result<Self, String> {
+        if imagereader::is_hdr_path(path) {
+            // Floating point images are linear, with sRGB primaries.
+            let (width, height, pixels) = imagereader::read_hdr_image(path)
+                .map_err(|err| format!("unable to load texture '{}': {}", path, err))?;
+            let texels = pixels.into_iter().map(|pixel| space.rec709_to_working(pixel)).collect();
+            return Ok(Self {texels, width, height});
+        }
+
+        let img = load_image::load_path(path)
+            .map_err(|err| format!("unable to load texture '{}': {}", path, err))?;
 
         let width = img.width;
         let height = img.height;
         let (rgba, _meta) = img.into_rgba();
         let (buf, _w, _h) = rgba.into_contiguous_buf();
 
-        return Self {image: buf, width: width, height: height};
+        // 8-bit images are sRGB encoded, so decode them once up front.
+        let srgb_to_linear = color::srgb_byte_table();
+        let texels = buf.iter()
+            .map(|pixel| space.rec709_to_working(Vec3::new(
+                srgb_to_linear[pixel.r as usize],
+                srgb_to_linear[pixel.g as usize],
+                srgb_to_linear[pixel.b as usize])))
+            .collect();
+
+        return Ok(Self {texels, width, height});
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn texel(&self, i: usize, j: usize) -> Vec3 {
+        // Texel i, j, counting from the top left.
+        return self.texels[self.width * j + i];
     }
 }
 
 impl Texture for ImageTexture {
     fn color_value(&self, u: f64, v: f64, _p: Vec3) -> Vec3 {
         // If we have no texture data, then return solid cyan as a debugging aid.
-        if self.height <= 0 { return Vec3::new(0.0,1.0,1.0); }
+        if self.height == 0 { return Vec3::new(0.0,1.0,1.0); }
 
         // Clamp input texture coordinates to [0,1] x [1,0]
         let u = Interval::new(0.0, 1.0).clamp(u);
         let v = 1.0 - Interval::new(0.0, 1.0).clamp(v);  // Flip V to image coordinates
 
-        let i = (u * self.width as f64) as usize;
-        let j = (v * self.height as f64) as usize;
-        let pixel = self.image[self.width * j + i];
-
-        let color_scale = 1.0 / 255.0;
-        return Vec3::new(color_scale * pixel.r as f64, color_scale * pixel.g as f64, color_scale * pixel.b as f64);
+        let i = ((u * self.width as f64) as usize).min(self.width - 1);
+        let j = ((v * self.height as f64) as usize).min(self.height - 1);
+        return self.texels[self.width * j + i];
     }
-}
\ No newline at end of file
+}
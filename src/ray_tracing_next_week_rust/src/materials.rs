@@ -1,3 +1,4 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
 use crate::texture::Texture;
 
@@ -6,7 +7,10 @@ use crate::vec3::Vec3;
 use crate::material::{Material, ScatterResult};
 use crate::ray::Ray;
 use crate::hitrecord::HitRecord;
-use crate::rtweekend::{self, random_unit_vector};
+use crate::rtweekend::{self, sample_cosine_direction, sample_unit_vector};
+use crate::onb::Onb;
+use crate::sampler::Sampler;
+use crate::color::WorkingSpace;
 
 pub struct Lambertian {
     texture: Arc<dyn Texture>
@@ -40,11 +44,27 @@ impl Lambertian {
 }
 
 impl Metal {
-    pub fn new(albedo: Vec3, fuzz: f64) -> Self {
+    pub fn new(albedo: Vec3, fuzz: f64, space: WorkingSpace) -> Self {
+        // The albedo is given with sRGB primaries, and kept in the scene's working space.
         Self {
-            albedo, fuzz
+            albedo: space.rec709_to_working(albedo), fuzz
         }
     }
+
+    fn fuzz_pdf(&self, reflected: Vec3, direction: Vec3) -> f64 {
+        // Fuzzed directions point at a uniform point on the sphere of radius fuzz about the tip of
+        // the unit reflection. The density over solid angle sums, over the points of that sphere
+        // the direction passes through, the area density stretched by distance and slant.
+        let direction = direction.normalized();
+        let b = vec3::dot(&direction, &reflected);
+        let discriminant = b * b - 1.0 + self.fuzz * self.fuzz;
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+        let root = discriminant.sqrt();
+        let distances_squared: f64 = [b - root, b + root].iter().filter(|&&t| t > 0.0).map(|t| t * t).sum();
+        return distances_squared / (4.0 * PI * self.fuzz * root);
+    }
 }
 
 impl Dielectric {
@@ -72,38 +92,71 @@ impl Isotropic {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterResult> {
-        let mut scatter_direction = hit_record.normal + random_unit_vector();
-
-        // Catch degenerate scatter direction
-        if scatter_direction.near_zero() {
-            scatter_direction = hit_record.normal;
-        }
-
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> {
+        // Cosine weighted about the normal, so the attenuation is just the reflectance.
+        let scatter_direction = Onb::new(hit_record.normal).transform(sample_cosine_direction(sampler.get_2d()));
         let scattered = Ray::new2(hit_record.p, scatter_direction, r_in.tm);
         let attenuation = self.texture.color_value(hit_record.u, hit_record.v, hit_record.p);
-        let scatter_result = ScatterResult::new(attenuation, scattered);
+        let pdf = self.scattering_pdf(r_in, hit_record, scatter_direction);
+        let scatter_result = ScatterResult::new(attenuation, scattered, pdf);
         return Some(scatter_result);
     }
+
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Vec3 {
+        let albedo = self.texture.color_value(hit_record.u, hit_record.v, hit_record.p);
+        return self.scattering_pdf(r_in, hit_record, direction) * albedo;
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+        let cos_theta = vec3::dot(&hit_record.normal, &direction.normalized());
+        return cos_theta.max(0.0) / PI;
+    }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        return self.texture.color_value(hit_record.u, hit_record.v, hit_record.p);
+    }
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterResult> {
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> {
         let reflected = rtweekend::reflect(r_in.direction, hit_record.normal);
-        let fuzzy_reflected = reflected.normalized() + (self.fuzz * rtweekend::random_unit_vector());
+        let fuzzy_reflected = reflected.normalized() + (self.fuzz * rtweekend::sample_unit_vector(sampler.get_2d()));
 
-        if vec3::dot(&reflected, &hit_record.normal) > 0.0 {
+        // Fuzzed reflections that end up below the surface are absorbed.
+        if vec3::dot(&reflected, &hit_record.normal) > 0.0 && vec3::dot(&fuzzy_reflected, &hit_record.normal) > 0.0 {
             let scattered = Ray::new2(hit_record.p, fuzzy_reflected, r_in.tm);
-            let scatter_result = ScatterResult::new(self.albedo, scattered);
+            if self.fuzz <= 0.0 {
+                return Some(ScatterResult::specular(self.albedo, scattered));
+            }
+            let pdf = self.fuzz_pdf(reflected.normalized(), fuzzy_reflected);
+            let scatter_result = ScatterResult::new(self.albedo, scattered, pdf);
             return Some(scatter_result);
         }
 
         return None;
     }
+
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Vec3 {
+        // Fuzzed reflections above the surface keep the albedo whichever direction they take.
+        return self.scattering_pdf(r_in, hit_record, direction) * self.albedo;
+    }
+
+    fn scattering_pdf(&self, r_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> f64 {
+        // Zero below the surface, where scatter absorbs the fuzzed reflection.
+        let reflected = rtweekend::reflect(r_in.direction, hit_record.normal);
+        if self.fuzz <= 0.0 || vec3::dot(&reflected, &hit_record.normal) <= 0.0 || vec3::dot(&direction, &hit_record.normal) <= 0.0 {
+            return 0.0;
+        }
+        return self.fuzz_pdf(reflected.normalized(), direction);
+    }
+
+    fn albedo(&self, _hit_record: &HitRecord) -> Vec3 {
+        return self.albedo;
+    }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterResult> {
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> {
         let attenuation = Vec3::new(1.0, 1.0, 1.0);
         let ri = if hit_record.front_face { 1.0 / self.refraction_index } else { self.refraction_index };
 
@@ -113,9 +166,11 @@ impl Material for Dielectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         let cannot_refract = ri * sin_theta > 1.0;
+        // Taken even when it isn't needed, so the following dimensions stay in step.
+        let u = sampler.get_1d();
 
         let direction = if cannot_refract
-         || reflectance(cos_theta, ri ) > rtweekend::random_double() {
+         || reflectance(cos_theta, ri ) > u {
              rtweekend::reflect(unit_direction, hit_record.normal)
             }
          else {
@@ -123,28 +178,61 @@ impl Material for Dielectric {
             };
 
         let scattered = Ray::new2(hit_record.p, direction, r_in.tm);
-        let scatter_result = ScatterResult::new(attenuation, scattered);
+        let scatter_result = ScatterResult::specular(attenuation, scattered);
         return Some(scatter_result);
     }
+
+    fn albedo(&self, _hit_record: &HitRecord) -> Vec3 {
+        return Vec3::new(1.0, 1.0, 1.0);
+    }
 }
 
 impl Material for DiffuseLight {
-    fn scatter(&self, _r_in: &Ray, _hit_record: &HitRecord) -> Option<ScatterResult> {
+    fn scatter(&self, _r_in: &Ray, _hit_record: &HitRecord, _sampler: &mut dyn Sampler) -> Option<ScatterResult> {
         return None;
     }
 
+    fn is_emissive(&self) -> bool {
+        return true;
+    }
+
     fn emitted(&self, u: f64, v: f64, p: Vec3) -> Vec3 {
         return self.texture.color_value(u, v, p);
     }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        // Lights don't reflect; their normalized emission keeps them distinct in the pass.
+        let emission = self.texture.color_value(hit_record.u, hit_record.v, hit_record.p);
+        let brightest = emission.x.max(emission.y).max(emission.z);
+        return if brightest > 1.0 { emission / brightest } else { emission };
+    }
 }
 
 impl Material for Isotropic {
-    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord) -> Option<ScatterResult> {
+    fn scatter(&self, r_in: &Ray, hit_record: &HitRecord, sampler: &mut dyn Sampler) -> Option<ScatterResult> {
         let attenuation = self.texture.color_value(hit_record.u, hit_record.v, hit_record.p);
-        let scattered = Ray::new2(hit_record.p, random_unit_vector(), r_in.tm);
-        let scatter_result = ScatterResult::new(attenuation, scattered);
+        let scattered = Ray::new2(hit_record.p, sample_unit_vector(sampler.get_2d()), r_in.tm);
+        let scatter_result = ScatterResult::new(attenuation, scattered, 1.0 / (4.0 * PI));
         return Some(scatter_result);
     }
+
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, direction: Vec3) -> Vec3 {
+        let albedo = self.texture.color_value(hit_record.u, hit_record.v, hit_record.p);
+        return self.scattering_pdf(r_in, hit_record, direction) * albedo;
+    }
+
+    fn scattering_pdf(&self, _r_in: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> f64 {
+        // Scattered directions are uniform over the sphere.
+        return 1.0 / (4.0 * PI);
+    }
+
+    fn is_volume(&self) -> bool {
+        return true;
+    }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        return self.texture.color_value(hit_record.u, hit_record.v, hit_record.p);
+    }
 }
 
 fn reflectance(cosine: f64, refraction_index: f64) -> f64 {
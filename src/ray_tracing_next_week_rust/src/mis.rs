@@ -0,0 +1,33 @@
+use clap::ValueEnum;
+
+// How light that two sampling strategies could have found is shared between them.
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum MisHeuristic {
+    Balance, // In proportion to the densities
+    Power    // In proportion to the squared densities, favouring the strategy that suits the light best
+}
+
+impl MisHeuristic {
+    pub fn weight(&self, pdf: f64, other_pdf: f64) -> f64 {
+        // Multiple importance sampling weight of a sample taken with density pdf, when other_pdf
+        // is the density of the other strategy that could have taken it.
+        let (a, b) = match self {
+            MisHeuristic::Balance => (pdf, other_pdf),
+            MisHeuristic::Power => (pdf * pdf, other_pdf * other_pdf)
+        };
+        if a + b <= 0.0 || !a.is_finite() {
+            return if a.is_infinite() { 1.0 } else { 0.0 };
+        }
+        return a / (a + b);
+    }
+
+    pub fn relative_weight(&self, pdf_ratio: f64) -> f64 {
+        // The weight of another strategy relative to that of the one a sample was taken with,
+        // given the ratio of their densities. Among several strategies, the sample's weight is 1
+        // over 1 plus the sum of these over the others.
+        return match self {
+            MisHeuristic::Balance => pdf_ratio,
+            MisHeuristic::Power => pdf_ratio * pdf_ratio
+        };
+    }
+}
@@ -1,6 +1,5 @@
 use crate::vec3::{Vec3, dot};
 use crate::rtweekend::*;
-use rand::seq::SliceRandom;
 const POINT_COUNT: usize = 256;
 
 pub struct Perlin {
@@ -12,24 +11,23 @@ pub struct Perlin {
 }
 
 impl Perlin {
-    pub fn new() -> Self {
-        let mut perm_x = [0 as i32; POINT_COUNT];
-        let mut perm_y = [0 as i32; POINT_COUNT];
-        let mut perm_z = [0 as i32; POINT_COUNT];
+    pub fn new(rng: &mut Rng) -> Self {
+        let mut perm_x = [0_i32; POINT_COUNT];
+        let mut perm_y = [0_i32; POINT_COUNT];
+        let mut perm_z = [0_i32; POINT_COUNT];
         // let mut randFloat= [0.0; POINT_COUNT];
         let mut rand_vec= [Vec3::new(0.0, 0.0, 0.0); POINT_COUNT];
 
         for n in 0..POINT_COUNT {
-            rand_vec[n] = random_vec3_min_max(-1.0, 1.0).normalized();
+            rand_vec[n] = random_vec3_min_max(rng, -1.0, 1.0).normalized();
             perm_x[n] = n as i32;
             perm_y[n] = n as i32;
             perm_z[n] = n as i32;
         }
 
-        let mut rng = rand::rng();
-        perm_x[..].shuffle(&mut rng);
-        perm_y[..].shuffle(&mut rng);
-        perm_z[..].shuffle(&mut rng);
+        shuffle(rng, &mut perm_x[..]);
+        shuffle(rng, &mut perm_y[..]);
+        shuffle(rng, &mut perm_z[..]);
 
         return Self {
             perm_x, perm_y, perm_z, /*randFloat,*/ rand_vec
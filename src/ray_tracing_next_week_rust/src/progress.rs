@@ -0,0 +1,88 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::film::Film;
+use crate::tiles::Tile;
+
+// Notified by Camera::render as tiles are finished, e.g. to show progress or stream the image.
+pub trait RenderCallback: Sync {
+    // Called once samples more samples, taken in the tile's pixels, have been added to the film.
+    // Calls are made one at a time, from the render threads.
+    fn tile_done(&self, tile: &Tile, samples: u64, film: &Film);
+}
+
+// Reports the fraction of the render done, the sampling rate and the estimated time left on
+// stderr. On a terminal the report is redrawn in place; otherwise a line is written now and then.
+pub struct StderrProgress {
+    total_tiles: u64,
+    total_samples: u64,
+    start: Instant,
+    state: Mutex<ProgressState>
+}
+
+struct ProgressState {
+    tiles_done: u64,
+    samples_done: u64,
+    last_report: Option<Instant>
+}
+
+impl StderrProgress {
+    pub fn new(total_tiles: u64, total_samples: u64) -> Self {
+        Self {
+            total_tiles,
+            total_samples: total_samples.max(1),
+            start: Instant::now(),
+            state: Mutex::new(ProgressState { tiles_done: 0, samples_done: 0, last_report: None })
+        }
+    }
+
+    pub fn finish(&self) {
+        // Writes the final report, ending the line. The render may have finished with fewer
+        // samples than the total, when adaptive sampling stops early.
+        let state = self.state.lock().unwrap();
+        self.report(&state, true);
+        if io::stderr().is_terminal() {
+            eprintln!();
+        }
+    }
+
+    fn report(&self, state: &ProgressState, finished: bool) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let fraction = if finished { 1.0 } else { state.samples_done as f64 / self.total_samples as f64 };
+        let rate = if elapsed > 0.0 { state.samples_done as f64 / elapsed } else { 0.0 };
+        let eta = if rate > 0.0 && !finished { (self.total_samples - state.samples_done.min(self.total_samples)) as f64 / rate } else { 0.0 };
+
+        let line = format!("{:5.1}%  tiles {}/{}  {:.2}M samples/s  elapsed {}  ETA {}",
+            100.0 * fraction, state.tiles_done, self.total_tiles, rate / 1e6,
+            format_duration(elapsed), format_duration(eta));
+        let mut err = io::stderr().lock();
+        if err.is_terminal() {
+            let _ = write!(err, "\r{}  ", line);
+        }
+        else {
+            let _ = writeln!(err, "{}", line);
+        }
+        let _ = err.flush();
+    }
+}
+
+impl RenderCallback for StderrProgress {
+    fn tile_done(&self, _tile: &Tile, samples: u64, _film: &Film) {
+        let mut state = self.state.lock().unwrap();
+        state.tiles_done += 1;
+        state.samples_done += samples;
+
+        let interval = if io::stderr().is_terminal() { Duration::from_millis(250) } else { Duration::from_secs(10) };
+        let now = Instant::now();
+        if state.last_report.is_none_or(|last_report| now - last_report >= interval) {
+            state.last_report = Some(now);
+            self.report(&state, false);
+        }
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    return format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+}
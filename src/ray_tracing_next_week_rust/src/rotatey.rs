@@ -1,11 +1,13 @@
 use std::sync::Arc;
 use crate::hittable::Hittable;
+use crate::material::Material;
 use crate::hitrecord::HitRecord;
 use crate::interval::Interval;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 use crate::aabb::AABB;
 use crate::rtweekend::degrees_to_radians;
+use crate::sampler::Sampler;
 
 pub struct RotateY {
     sin_theta: f64,
@@ -54,7 +56,7 @@ impl RotateY {
 }
 
 impl Hittable for RotateY {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
         // Translate the ray from world space to object space.
 
         let origin = Vec3::new(
@@ -72,7 +74,7 @@ impl Hittable for RotateY {
         let rotated_r = Ray::new2(origin, direction, ray.tm);
 
         // Determine whether an intersection exists in object space (and if so, where).
-        let hit_result = self.hittable.hit(&rotated_r, ray_t);
+        let hit_result = self.hittable.hit(&rotated_r, ray_t, sampler);
         if let Some(hit) = hit_result {
             // Transform the intersection from object space back to world space.
 
@@ -97,4 +99,8 @@ impl Hittable for RotateY {
     fn bounding_box(&self) -> AABB {
         return self.bbox;
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<dyn Material>>) {
+        self.hittable.collect_materials(materials);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::hittable::Hittable;
+use crate::hittables::Hittables;
+use crate::background::Background;
+use crate::color::WorkingSpace;
+use crate::material::Material;
+
+// What the camera renders: the world, the emissive objects in it that are sampled for direct
+// lighting, and the light from beyond it, with colors in the working space the scene was built in.
+// The materials of the world are numbered as it's built, in the order the world lists them.
+pub struct Scene {
+    pub world: Box<dyn Hittable>,
+    pub lights: Hittables,
+    pub background: Box<dyn Background>,
+    pub working_space: WorkingSpace,
+    material_ids: HashMap<usize, usize> // Number of each material, by address
+}
+
+impl Scene {
+    pub fn new(world: Box<dyn Hittable>, lights: Hittables, background: Box<dyn Background>, working_space: WorkingSpace) -> Self {
+        let mut materials: Vec<Arc<dyn Material>> = Vec::new();
+        world.collect_materials(&mut materials);
+        let mut material_ids: HashMap<usize, usize> = HashMap::new();
+        for material in materials.iter() {
+            let next_id = material_ids.len();
+            material_ids.entry(address(material)).or_insert(next_id);
+        }
+
+        Self {
+            world, lights, background, working_space, material_ids
+        }
+    }
+
+    pub fn material_id(&self, material: &Arc<dyn Material + Sync>) -> Option<usize> {
+        // The number given to a material of the world, the same in every pass and run.
+        return self.material_ids.get(&address(material)).copied();
+    }
+}
+
+fn address<T: ?Sized>(material: &Arc<T>) -> usize {
+    return Arc::as_ptr(material) as *const () as usize;
+}
@@ -6,19 +6,82 @@ use crate::material::Material;
 use crate::hittable::Hittable;
 use crate::hittables::Hittables;
 use crate::vec3::Vec3;
-use crate::rtweekend::{self, random_double_min_max};
+use crate::color::WorkingSpace;
+use crate::rtweekend::{self, random_double_min_max, Rng};
 use crate::sphere::Sphere;
 use crate::bvhnode::BvhNode;
 use crate::texture::Texture;
 use crate::textures::{CheckerTexture, SolidColorTexture, NoiseTexture};
 use crate::imagetexture::ImageTexture;
-use crate::camera::Camera;
+use crate::camera::CameraBuilder;
+use crate::background::Background;
+use crate::scene::Scene;
+use crate::backgrounds::SolidBackground;
+use crate::physicalsky::PhysicalSky;
 use crate::quad::Quad;
 use crate::translate::Translate;
 use crate::rotatey::RotateY;
 use crate::constantmedium::ConstantMedium;
 
-pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
+pub struct SceneInfo {
+    pub name: &'static str,
+    pub description: &'static str
+}
+
+// Scenes in the order of the book; the 1-based position is also accepted on the command line.
+pub const SCENES: [SceneInfo; 10] = [
+    SceneInfo { name: "bouncing-spheres", description: "Random moving spheres on a checkered ground" },
+    SceneInfo { name: "checkered-spheres", description: "Two checker textured spheres" },
+    SceneInfo { name: "earth", description: "Image textured globe (needs earthmap.jpg)" },
+    SceneInfo { name: "perlin-spheres", description: "Perlin noise textured spheres" },
+    SceneInfo { name: "quads", description: "Five coloured quads" },
+    SceneInfo { name: "simple-light", description: "Perlin spheres lit by a quad light" },
+    SceneInfo { name: "cornell-box", description: "Cornell box" },
+    SceneInfo { name: "cornell-smoke", description: "Cornell box with smoke filled blocks" },
+    SceneInfo { name: "final", description: "Final scene of the book (needs earthmap.jpg)" },
+    SceneInfo { name: "sunlit-spheres", description: "The bouncing spheres under a daylight sky and sun" }
+];
+
+// The camera settings of a scene, and what the camera looks at.
+pub type SceneParts = (CameraBuilder, Scene);
+
+fn lights(world: &[Arc<dyn Hittable>]) -> Hittables {
+    // The emissive objects at the top level of the world, which are sampled for direct lighting.
+    let lights: Vec<Arc<dyn Hittable>> = world.iter().filter(|object| object.is_emissive()).cloned().collect();
+    return Hittables::new(&lights);
+}
+
+pub fn find_scene(name_or_number: &str) -> Option<&'static SceneInfo> {
+    if let Ok(number) = name_or_number.parse::<usize>() {
+        return number.checked_sub(1).and_then(|index| SCENES.get(index));
+    }
+    return SCENES.iter().find(|scene| scene.name == name_or_number);
+}
+
+pub fn load_scene(scene: &SceneInfo, texture_path: &str, rng: &mut Rng, space: WorkingSpace) -> Result<SceneParts, String> {
+    match scene.name {
+        "bouncing-spheres" => Ok(bouncing_spheres(rng, space)),
+        "checkered-spheres" => Ok(checkered_spheres(space)),
+        "earth" => earth(texture_path, space),
+        "perlin-spheres" => Ok(perlin_spheres(rng, space)),
+        "quads" => Ok(quads(space)),
+        "simple-light" => Ok(simple_light(rng, space)),
+        "cornell-box" => Ok(cornell_box(space)),
+        "cornell-smoke" => Ok(cornell_smoke(space)),
+        "final" => final_scene(texture_path, rng, space),
+        "sunlit-spheres" => Ok(sunlit_spheres(rng, space)),
+        _ => Err(format!("unrecognised scene '{}'", scene.name))
+    }
+}
+
+pub fn sunlit_spheres(rng: &mut Rng, space: WorkingSpace) -> SceneParts {
+    let (camera, mut scene) = bouncing_spheres(rng, space);
+    scene.background = Box::new(PhysicalSky::new(35.0, 120.0, 3.0, space));
+
+    return (camera, scene);
+}
+
+pub fn final_scene(texture_path: &str, rng: &mut Rng, space: WorkingSpace) -> Result<SceneParts, String> {
     let aspect_ratio = 1.0;
     let image_width = 300;
     let vfov = 40.0;
@@ -26,11 +89,19 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
     let look_at = Vec3::new(278.0, 278.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.0, 0.0, 0.0), space));
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
-    let ground: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.48, 0.83, 0.53)));
+    let ground: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.48, 0.83, 0.53), space));
     let ground_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&ground)));
 
     let mut boxes: Vec<Arc<dyn Hittable>> = Vec::new();
@@ -43,7 +114,7 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
             let z0 = -1000.0 + (j as f64 * w);
             let y0 = 0.0;
             let x1 = x0 + w;
-            let y1 = random_double_min_max(1.0, 101.0);
+            let y1 = random_double_min_max(rng, 1.0, 101.0);
             let z1 = z0 + w;
 
             let the_box: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(x0, y0, z0), Vec3::new(x1, y1, z1), Arc::clone(&ground_material)));
@@ -56,7 +127,7 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
 
     world.push(boxes_hittable);
 
-    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(7.0, 7.0, 7.0)));
+    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(7.0, 7.0, 7.0), space));
     let light_mat: Arc<dyn Material> = Arc::new(DiffuseLight::new(Arc::clone(&light_tex)));
 
     let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(123.0, 554.0, 147.0), Vec3::new(300.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 265.0), Arc::clone(&light_mat)));
@@ -65,7 +136,7 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
     let center1 = Vec3::new(400.0, 400.0, 200.0);
     let center2 = center1 + Vec3::new(30.0, 0.0, 0.0);
 
-    let sphere_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.7, 0.3, 0.1)));
+    let sphere_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.7, 0.3, 0.1), space));
     let sphere_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&sphere_tex)));
     let sphere_1: Arc<dyn Hittable> = Arc::new(Sphere::new_moving(center1, center2, 50.0, Arc::clone(&sphere_material)));
     world.push(sphere_1);
@@ -74,33 +145,33 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
     let sphere_2: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(260.0, 150.0, 45.0), 50.0, Arc::clone(&dielectric_mat)));
     world.push(sphere_2);
 
-    let sphere_material3: Arc<dyn Material> = Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.9),1.0));
+    let sphere_material3: Arc<dyn Material> = Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.9),1.0, space));
     let sphere_3: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 150.0, 145.0), 50.0, Arc::clone(&sphere_material3)));
     world.push(sphere_3);
 
     let boundary: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(360.0, 150.0, 145.0), 70.0, Arc::clone(&dielectric_mat)));
-    let constant_medium: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(Arc::clone(&boundary), Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.4, 0.9))), 0.2));
+    let constant_medium: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(Arc::clone(&boundary), Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.4, 0.9), space)), 0.2));
     world.push(boundary);
     world.push(constant_medium);
     let boundary_2: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 5000.0, Arc::clone(&dielectric_mat)));
-    let constant_medium2: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(Arc::clone(&boundary_2), Arc::new(SolidColorTexture::new(Vec3::new(1.0, 1.0, 1.0))), 0.0001));
+    let constant_medium2: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(Arc::clone(&boundary_2), Arc::new(SolidColorTexture::new(Vec3::new(1.0, 1.0, 1.0), space)), 0.0001));
     world.push(boundary_2);
     world.push(constant_medium2);
 
     let earth_texture_path = Path::new(texture_path).join("earthmap.jpg");
 
-    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(earth_texture_path.to_str().unwrap()));
+    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(&earth_texture_path.to_string_lossy(), space)?);
     let earth_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&earth_texture)));
     let globe : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(400.0, 200.0, 400.0), 100.0, Arc::clone(&earth_material)));
     world.push(globe);
 
-    let per_tex: Arc<dyn Texture> = Arc::new(NoiseTexture::new(0.2));
+    let per_tex: Arc<dyn Texture> = Arc::new(NoiseTexture::new(0.2, rng));
     let per_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&per_tex)));
     let sphere_per: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(220.0, 280.0, 300.0), 80.0, Arc::clone(&per_material)));
     world.push(sphere_per);
 
     let mut spheres: Vec<Arc<dyn Hittable>> = Vec::new();
-    let spheres_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73)));
+    let spheres_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73), space));
     let spheres_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&spheres_tex)));
     for i in 0..10 {
         for j in 0..10 {
@@ -120,10 +191,12 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
     let hittables = Hittables::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable);
+    let scene = Scene::new(world_hittable, lights(&world), background, space);
+
+    return Ok((camera, scene));
 }
 
-pub fn cornell_smoke() -> (Camera, Box<dyn Hittable>) {
+pub fn cornell_smoke(space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 40.0;
@@ -131,18 +204,26 @@ pub fn cornell_smoke() -> (Camera, Box<dyn Hittable>) {
     let look_at = Vec3::new(278.0, 278.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.0, 0.0, 0.0), space));
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
-    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.65, 0.05, 0.05)));
+    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.65, 0.05, 0.05), space));
     let red_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&red)));
-    let white: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73)));
+    let white: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73), space));
     let white_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&white)));
-    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.12, 0.45, 0.15)));
+    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.12, 0.45, 0.15), space));
     let green_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&green)));
 
-    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(7.0, 7.0, 7.0)));
+    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(7.0, 7.0, 7.0), space));
     let light_mat: Arc<dyn Material> = Arc::new(DiffuseLight::new(Arc::clone(&light_tex)));
 
     let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), Arc::clone(&green_material)));
@@ -161,22 +242,24 @@ pub fn cornell_smoke() -> (Camera, Box<dyn Hittable>) {
     let box1: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 330.0, 165.0), Arc::clone(&white_material)));
     let box1: Arc<dyn Hittable> = Arc::new(RotateY::new(15.0, box1));
     let box1: Arc<dyn Hittable> = Arc::new(Translate::new(Vec3::new(265.0, 0.0, 295.0), box1));
-    let box1: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(box1, Arc::new(SolidColorTexture::new(Vec3::new(0.0, 0.0, 0.0))), 0.01));
+    let box1: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(box1, Arc::new(SolidColorTexture::new(Vec3::new(0.0, 0.0, 0.0), space)), 0.01));
     world.push(box1);
     let box2: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 165.0, 165.0), Arc::clone(&white_material)));
     let box2: Arc<dyn Hittable> = Arc::new(RotateY::new(-18.0, box2));
     let box2: Arc<dyn Hittable> = Arc::new(Translate::new(Vec3::new(130.0, 0.0, 65.0), box2));
-    let box2: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(box2, Arc::new(SolidColorTexture::new(Vec3::new(1.0, 1.0, 1.0))), 0.01));
+    let box2: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(box2, Arc::new(SolidColorTexture::new(Vec3::new(1.0, 1.0, 1.0), space)), 0.01));
     world.push(box2);
 
     // let hittables = Hittables::new(&world[..]);
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable);
+    let scene = Scene::new(world_hittable, lights(&world), background, space);
+
+    return (camera, scene);
 }
 
-pub fn cornell_box() -> (Camera, Box<dyn Hittable>) {
+pub fn cornell_box(space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 40.0;
@@ -184,18 +267,26 @@ pub fn cornell_box() -> (Camera, Box<dyn Hittable>) {
     let look_at = Vec3::new(278.0, 278.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.0, 0.0, 0.0), space));
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
-    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.65, 0.05, 0.05)));
+    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.65, 0.05, 0.05), space));
     let red_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&red)));
-    let white: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73)));
+    let white: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73), space));
     let white_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&white)));
-    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.12, 0.45, 0.15)));
+    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.12, 0.45, 0.15), space));
     let green_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&green)));
 
-    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(15.0, 15.0, 15.0)));
+    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(15.0, 15.0, 15.0), space));
     let light_mat: Arc<dyn Material> = Arc::new(DiffuseLight::new(Arc::clone(&light_tex)));
 
     let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), Arc::clone(&green_material)));
@@ -224,10 +315,12 @@ pub fn cornell_box() -> (Camera, Box<dyn Hittable>) {
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable);
+    let scene = Scene::new(world_hittable, lights(&world), background, space);
+
+    return (camera, scene);
 }
 
-pub fn simple_light() -> (Camera, Box<dyn Hittable>) {
+pub fn simple_light(rng: &mut Rng, space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -235,18 +328,26 @@ pub fn simple_light() -> (Camera, Box<dyn Hittable>) {
     let look_at = Vec3::new(0.0, 2.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.0, 0.0, 0.0), space));
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
-    let per_tex: Arc<dyn Texture> = Arc::new(NoiseTexture::new(4.0));
+    let per_tex: Arc<dyn Texture> = Arc::new(NoiseTexture::new(4.0, rng));
     let per_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&per_tex)));
     let sphere_1: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, Arc::clone(&per_material)));
     let sphere_2: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 2.0, 0.0), 2.0, Arc::clone(&per_material)));
     world.push(sphere_1);
     world.push(sphere_2);
 
-    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(4.0, 4.0, 4.0)));
+    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(4.0, 4.0, 4.0), space));
     let light_mat: Arc<dyn Material> = Arc::new(DiffuseLight::new(Arc::clone(&light_tex)));
     let quad: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(3.0, 1.0, -2.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Arc::clone(&light_mat)));
     world.push(quad);
@@ -255,10 +356,12 @@ pub fn simple_light() -> (Camera, Box<dyn Hittable>) {
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable);
+    let scene = Scene::new(world_hittable, lights(&world), background, space);
+
+    return (camera, scene);
 }
 
-pub fn quads() -> (Camera, Box<dyn Hittable>) {
+pub fn quads(space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 80.0;
@@ -266,19 +369,27 @@ pub fn quads() -> (Camera, Box<dyn Hittable>) {
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
-    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0, 0.2, 0.2)));
+    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0, 0.2, 0.2), space));
     let left_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&red)));
-    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 1.0, 0.2)));
+    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 1.0, 0.2), space));
     let back_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&green)));
-    let blue: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.2, 1.0)));
+    let blue: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.2, 1.0), space));
     let right_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&blue)));
-    let orange: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0,0.5, 0.0)));
+    let orange: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0,0.5, 0.0), space));
     let upper_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&orange)));
-    let teal: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2,0.8, 0.8)));
+    let teal: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2,0.8, 0.8), space));
     let lower_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&teal)));
 
     let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(-3.0, -2.0, 5.0), Vec3::new(0.0, 0.0, -4.0), Vec3::new(0.0, 4.0, 0.0), Arc::clone(&left_material)));
@@ -297,10 +408,12 @@ pub fn quads() -> (Camera, Box<dyn Hittable>) {
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable);
+    let scene = Scene::new(world_hittable, lights(&world), background, space);
+
+    return (camera, scene);
 }
 
-pub fn perlin_spheres() -> (Camera, Box<dyn Hittable>) {
+pub fn perlin_spheres(rng: &mut Rng, space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -308,11 +421,19 @@ pub fn perlin_spheres() -> (Camera, Box<dyn Hittable>) {
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
-    let perlin_texture: Arc<dyn Texture> = Arc::new(NoiseTexture::new(1.0));
+    let perlin_texture: Arc<dyn Texture> = Arc::new(NoiseTexture::new(1.0, rng));
     let perlin_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&perlin_texture)));
 
     let sphere_1 : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, Arc::clone(&perlin_material)));
@@ -323,10 +444,12 @@ pub fn perlin_spheres() -> (Camera, Box<dyn Hittable>) {
     let hittables = Hittables::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable);
+    let scene = Scene::new(world_hittable, lights(&world), background, space);
+
+    return (camera, scene);
 }
 
-pub fn earth(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
+pub fn earth(texture_path: &str, space: WorkingSpace) -> Result<SceneParts, String> {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -334,12 +457,20 @@ pub fn earth(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
     let earth_texture_path = Path::new(texture_path).join("earthmap.jpg");
-    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(earth_texture_path.to_str().unwrap()));
+    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(&earth_texture_path.to_string_lossy(), space)?);
     let earth_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&earth_texture)));
 
     let globe : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 2.0, Arc::clone(&earth_material)));
@@ -349,10 +480,12 @@ pub fn earth(texture_path: &str) -> (Camera, Box<dyn Hittable>) {
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable);
+    let scene = Scene::new(world_hittable, lights(&world), background, space);
+
+    return Ok((camera, scene));
 }
 
-pub fn checkered_spheres() -> (Camera, Box<dyn Hittable>) {
+pub fn checkered_spheres(space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -362,12 +495,20 @@ pub fn checkered_spheres() -> (Camera, Box<dyn Hittable>) {
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
-    let even = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.3, 0.1)));
-    let odd = Arc::new(SolidColorTexture::new(Vec3::new(0.9, 0.9, 0.9)));
+    let even = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.3, 0.1), space));
+    let odd = Arc::new(SolidColorTexture::new(Vec3::new(0.9, 0.9, 0.9), space));
     let checker: Arc<dyn Texture> = Arc::new(CheckerTexture::new(0.32, even, odd));
     let checker_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&checker)));
 
@@ -381,10 +522,12 @@ pub fn checkered_spheres() -> (Camera, Box<dyn Hittable>) {
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable);
+    let scene = Scene::new(world_hittable, lights(&world), background, space);
+
+    return (camera, scene);
 }
 
-pub fn bouncing_spheres() -> (Camera, Box<dyn Hittable>) {
+pub fn bouncing_spheres(rng: &mut Rng, space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -392,19 +535,29 @@ pub fn bouncing_spheres() -> (Camera, Box<dyn Hittable>) {
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.6;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let focus_dist = 10.0;
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle)
+        .focus_distance(focus_dist);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
     let mut materials: Vec<Arc<dyn Material>> = Vec::new();
 
-    let material_ground: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(Vec3::new(0.5, 0.5, 0.5)))));
+    let material_ground: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(Vec3::new(0.5, 0.5, 0.5), space))));
     let material_1: Arc<dyn Material> = Arc::new(Dielectric::new(1.5));
-    let material_2: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(Vec3::new(0.4, 0.2, 0.1)))));
-    let material_3: Arc<dyn Material> = Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0));
+    let material_2: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(Vec3::new(0.4, 0.2, 0.1), space))));
+    let material_3: Arc<dyn Material> = Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0, space));
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
-    let even = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.3, 0.1)));
-    let odd = Arc::new(SolidColorTexture::new(Vec3::new(0.9, 0.9, 0.9)));
+    let even = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.3, 0.1), space));
+    let odd = Arc::new(SolidColorTexture::new(Vec3::new(0.9, 0.9, 0.9), space));
     let checker: Arc<dyn Texture> = Arc::new(CheckerTexture::new(0.32, even, odd));
     let checker_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&checker)));
     let checker_sphere : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, Arc::clone(&checker_material)));
@@ -424,25 +577,25 @@ pub fn bouncing_spheres() -> (Camera, Box<dyn Hittable>) {
 
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat = rtweekend::random_double();
-            let center = Vec3::new((a as f64) + 0.9 * rtweekend::random_double(), 0.2, (b as f64) + 0.9 * rtweekend::random_double());
+            let choose_mat = rtweekend::random_double(rng);
+            let center = Vec3::new((a as f64) + 0.9 * rtweekend::random_double(rng), 0.2, (b as f64) + 0.9 * rtweekend::random_double(rng));
 
             if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
                 if choose_mat < 0.8 {
                     // diffuse
-                    let albedo = rtweekend::random_vec3() * rtweekend::random_vec3();
-                    let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(albedo))));
+                    let albedo = rtweekend::random_vec3(rng) * rtweekend::random_vec3(rng);
+                    let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(albedo, space))));
                     materials.push(Arc::clone(&mat));
 
-                    let center_2 = center + Vec3::new(0.0, random_double_min_max(0.0, 0.5), 0.0);
+                    let center_2 = center + Vec3::new(0.0, random_double_min_max(rng, 0.0, 0.5), 0.0);
                     let sphere = Sphere::new_moving(center, center_2, 0.2, Arc::clone(&mat));
                     let hittable: Arc<dyn Hittable> = Arc::new(sphere);
                     world.push(hittable);
                 } else if choose_mat < 0.95 {
                     // metal
-                    let albedo = rtweekend::random_vec3_min_max(0.5, 1.0);
-                    let fuzz = rtweekend::random_double_min_max(0.0, 0.5);
-                    let mat: Arc<dyn Material> = Arc::new(Metal::new(albedo, fuzz));
+                    let albedo = rtweekend::random_vec3_min_max(rng, 0.5, 1.0);
+                    let fuzz = rtweekend::random_double_min_max(rng, 0.0, 0.5);
+                    let mat: Arc<dyn Material> = Arc::new(Metal::new(albedo, fuzz, space));
                     materials.push(Arc::clone(&mat));
 
                     let hittable: Arc<dyn Hittable> = Arc::new(Sphere::new(center, 0.2, Arc::clone(&mat)));
@@ -463,7 +616,9 @@ pub fn bouncing_spheres() -> (Camera, Box<dyn Hittable>) {
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable);
+    let scene = Scene::new(world_hittable, lights(&world), background, space);
+
+    return (camera, scene);
 }
 
 fn box3(a: Vec3, b: Vec3, mat: Arc<dyn Material>) -> Hittables {
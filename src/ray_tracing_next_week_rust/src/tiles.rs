@@ -0,0 +1,114 @@
+use clap::ValueEnum;
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum TileOrder {
+    Scanline, // Rows of tiles from the top left
+    Spiral,   // Outwards from the center of the image
+    Hilbert   // Along a Hilbert curve, keeping consecutive tiles close together
+}
+
+// A rectangle of pixels, from (x0, y0) up to but not including (x1, y1).
+#[derive(Copy, Clone, Debug)]
+pub struct Tile {
+    pub x0: u64,
+    pub y0: u64,
+    pub x1: u64,
+    pub y1: u64
+}
+
+impl Tile {
+    pub fn pixel_count(&self) -> u64 {
+        return (self.x1 - self.x0) * (self.y1 - self.y0);
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = (u64, u64)> {
+        // The tile's pixel coordinates, row by row.
+        let (x0, x1) = (self.x0, self.x1);
+        return (self.y0..self.y1).flat_map(move |y| (x0..x1).map(move |x| (x, y)));
+    }
+}
+
+pub fn tile_count(region: &Tile, tile_size: u64) -> u64 {
+    let tile_size = tile_size.max(1);
+    return (region.x1 - region.x0).div_ceil(tile_size) * (region.y1 - region.y0).div_ceil(tile_size);
+}
+
+pub fn tiles(region: &Tile, tile_size: u64, order: TileOrder) -> Vec<Tile> {
+    // Splits the region of the image into tiles of at most tile_size x tile_size pixels, in
+    // rendering order.
+    let tile_size = tile_size.max(1);
+    let columns = (region.x1 - region.x0).div_ceil(tile_size);
+    let rows = (region.y1 - region.y0).div_ceil(tile_size);
+
+    let cells: Vec<(u64, u64)> = match order {
+        TileOrder::Scanline => (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row))).collect(),
+        TileOrder::Spiral => spiral(columns, rows),
+        TileOrder::Hilbert => {
+            let mut cells: Vec<(u64, u64)> = (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row))).collect();
+            let side = columns.max(rows).next_power_of_two();
+            cells.sort_by_key(|(column, row)| hilbert_index(side, *column, *row));
+            cells
+        }
+    };
+
+    return cells.into_iter().map(|(column, row)| Tile {
+        x0: region.x0 + column * tile_size,
+        y0: region.y0 + row * tile_size,
+        x1: (region.x0 + (column + 1) * tile_size).min(region.x1),
+        y1: (region.y0 + (row + 1) * tile_size).min(region.y1)
+    }).collect();
+}
+
+fn spiral(columns: u64, rows: u64) -> Vec<(u64, u64)> {
+    // Walks a square spiral around the center cell - right, down, left, up, with the legs growing
+    // every second turn - keeping the cells that are inside the grid.
+    let count = (columns * rows) as usize;
+    let mut cells = Vec::with_capacity(count);
+    let (mut x, mut y) = ((columns / 2) as i64, (rows / 2) as i64);
+    let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let mut leg_length = 1;
+    let mut direction = 0;
+
+    let inside = |x: i64, y: i64| x >= 0 && y >= 0 && x < columns as i64 && y < rows as i64;
+    if inside(x, y) {
+        cells.push((x as u64, y as u64));
+    }
+    while cells.len() < count {
+        for _leg in 0..2 {
+            let (dx, dy) = directions[direction];
+            for _step in 0..leg_length {
+                x += dx;
+                y += dy;
+                if inside(x, y) {
+                    cells.push((x as u64, y as u64));
+                }
+            }
+            direction = (direction + 1) % 4;
+        }
+        leg_length += 1;
+    }
+    return cells;
+}
+
+fn hilbert_index(side: u64, x: u64, y: u64) -> u64 {
+    // Distance along the Hilbert curve filling a side x side grid, side being a power of two.
+    let (mut x, mut y) = (x, y);
+    let mut index = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = ((x & s) > 0) as u64;
+        let ry = ((y & s) > 0) as u64;
+        index += s * s * ((3 * rx) ^ ry);
+
+        // Rotate the quadrant so the sub-curve is in its canonical orientation.
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    return index;
+}
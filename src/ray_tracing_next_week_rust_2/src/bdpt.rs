@@ -0,0 +1,465 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::integrator::{Integrator, Splat};
+use crate::camera::Camera;
+use crate::scene::Scene;
+use crate::hittable::{Hittable, SurfaceSample};
+use crate::hitrecord::HitRecord;
+use crate::ray::Ray;
+use crate::interval::Interval;
+use crate::sampler::Sampler;
+use crate::vec3::{self, Vec3};
+use crate::mis::MisHeuristic;
+use crate::onb::Onb;
+use crate::rtweekend::sample_cosine_direction;
+use crate::material::Material;
+
+// Bidirectional path tracing, after Veach's thesis and PBRT: a subpath traced from the camera and
+// one traced from a point picked on the lights are joined, every prefix of one to every prefix of
+// the other, and each way of building a path is weighted by multiple importance sampling against
+// the other ways the same path could have been built. Light subpaths joined straight to the lens
+// reach other pixels than the one being sampled, so their light is splatted.
+// Only camera subpaths find the background, by leaving the scene.
+pub struct BdptIntegrator {
+    max_depth: u16,               // Maximum number of segments of a path
+    russian_roulette_depth: u16,  // Bounces each subpath gets before Russian roulette may end it
+    mis_heuristic: MisHeuristic,  // Weighting of the ways of building each path
+    camera: Camera                // For joining light subpaths to the lens
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum VertexKind {
+    Camera,  // On the lens
+    Light,   // Picked on a light
+    Surface, // Where a subpath met a surface
+    Medium   // Where a subpath scattered in a medium
+}
+
+// A vertex of a camera or light subpath. Its densities are over area: of picking it from the
+// previous vertex of its subpath, and of picking it from the next one, as the subpath from the
+// other end would have.
+struct Vertex {
+    kind: VertexKind,
+    p: Vec3,
+    normal: Vec3,           // Of the surface or light; unused on the lens and in media
+    hit: Option<HitRecord>, // For surfaces and media
+    beta: Vec3,             // Product of the BSDFs and cosines over the densities, up to the vertex
+    delta: bool,            // Whether it scattered specularly, so that it can't be joined to
+    pdf_fwd: f64,
+    pdf_rev: f64
+}
+
+// The densities of a vertex along the path a strategy built.
+#[derive(Copy, Clone, Debug)]
+struct Densities {
+    fwd: f64,
+    rev: f64,
+    delta: bool
+}
+
+impl BdptIntegrator {
+    pub fn new(max_depth: u16, russian_roulette_depth: u16, mis_heuristic: MisHeuristic, camera: Camera) -> Self {
+        Self {
+            max_depth, russian_roulette_depth, mis_heuristic, camera
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn random_walk(&self, mut ray: Ray, mut beta: Vec3, mut pdf: f64, from_camera: bool, max_vertices: usize, scene: &Scene, sampler: &mut dyn Sampler, path: &mut Vec<Vertex>, mats: &[Arc<dyn Material>]) -> Vec3 {
+        // Extends the subpath, which holds its first vertex, along the ray and then by scattering,
+        // until it has max_vertices vertices or ends. pdf is the density over solid angle of the
+        // ray's direction. Camera subpaths that leave the scene return the background they see.
+        let start = beta.max_component();
+        loop {
+            let Some(hit) = scene.world.hit(&ray, &Interval::new(0.0001, f64::INFINITY), sampler) else {
+                if from_camera {
+                    return beta * scene.background.color(ray.direction.normalized());
+                }
+                break;
+            };
+            let mut vertex = Vertex::scattering(hit, beta, mats);
+            let previous = path.len() - 1;
+            vertex.pdf_fwd = path[previous].area_density(pdf, &vertex);
+            path.push(vertex);
+            if path.len() >= max_vertices {
+                break;
+            }
+
+            let vertex = &path[previous + 1];
+            let Some(hit) = vertex.hit.as_ref() else {
+                break;
+            };
+            let Some(scattered) = mats[hit.material as usize].scatter(&ray, hit, sampler) else {
+                break;
+            };
+            let to_previous = -ray.direction;
+            let direction = scattered.scattered.direction;
+            let (weight, pdf_rev) = if scattered.specular {
+                (scattered.attenuation, 0.0)
+            } else {
+                if scattered.pdf <= 0.0 {
+                    break;
+                }
+                // Camera subpaths carry light arriving from the direction picked, light subpaths
+                // carry it on towards it.
+                let f = if from_camera { vertex.f(to_previous, direction, ray.tm, mats) } else { vertex.f(direction, to_previous, ray.tm, mats) };
+                let pdf_rev = vertex.scattering_pdf(direction, to_previous, ray.tm, mats);
+                ((vertex.abs_cos(direction) / scattered.pdf) * f, vertex.area_density(pdf_rev, &path[previous]))
+            };
+            pdf = if scattered.specular { 0.0 } else { scattered.pdf };
+            path[previous + 1].delta = scattered.specular;
+            path[previous].pdf_rev = pdf_rev;
+            beta = beta * weight;
+            ray = scattered.scattered;
+
+            if path.len() > self.russian_roulette_depth as usize {
+                // As in the path tracer, relative to the throughput the subpath started with.
+                let survival = (beta.max_component() / start).min(1.0);
+                if sampler.get_1d() >= survival {
+                    break;
+                }
+                beta = beta / survival;
+            }
+        }
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn connect(&self, scene: &Scene, light_path: &[Vertex], camera_path: &[Vertex], s: usize, t: usize, time: f64, sampler: &mut dyn Sampler, splats: &mut Vec<Splat>, mats: &[Arc<dyn Material>]) -> Vec3 {
+        // The light carried by the path made of the first s vertices of the light subpath and the
+        // first t of the camera subpath, joined by a shadow ray, weighted against the other ways
+        // of building it. Light joined straight to the lens goes to splats instead.
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        let camera_end = &camera_path[t - 1];
+        if s == 0 {
+            // The camera subpath found a light by itself.
+            let Some(hit) = camera_end.hit.as_ref() else {
+                return zero;
+            };
+            if camera_end.kind != VertexKind::Surface || !mats[hit.material as usize].is_emissive() {
+                return zero;
+            }
+            let emitted = mats[hit.material as usize].emitted(hit.u, hit.v, hit.p);
+            return self.mis_weight(scene, light_path, camera_path, None, s, t, time, mats) * (camera_end.beta * emitted);
+        }
+
+        if t == 1 {
+            let light_end = &light_path[s - 1];
+            if light_end.delta {
+                return zero;
+            }
+            let Some(lens) = self.camera.sample_lens(light_end.p, sampler) else {
+                return zero;
+            };
+            let to_lens = lens.p - light_end.p;
+            let f = light_end.f(to_lens, light_path[s - 2].p - light_end.p, time, mats);
+            let radiance = (lens.importance * light_end.abs_cos(to_lens)) * (f * light_end.beta);
+            if radiance.max_component() <= 0.0 || !visible(scene, light_end.p, lens.p, time, sampler) {
+                return zero;
+            }
+            let lens_vertex = Vertex::camera(lens.p);
+            let weight = self.mis_weight(scene, light_path, camera_path, Some(&lens_vertex), s, t, time, mats);
+            splats.push(Splat { x: lens.x, y: lens.y, radiance: weight * radiance });
+            return zero;
+        }
+
+        // A light subpath of just its first vertex is joined through a fresh point on the lights
+        // instead, as the path tracer samples them.
+        let sampled_light = if s == 1 {
+            let Some(sample) = scene.lights.sample_surface(time, sampler) else {
+                return zero;
+            };
+            Some(Vertex::light(sample, mats))
+        } else {
+            None
+        };
+        let light_end = sampled_light.as_ref().unwrap_or(&light_path[s - 1]);
+        if light_end.delta || camera_end.delta {
+            return zero;
+        }
+        let to_light = light_end.p - camera_end.p;
+        // Lights give off the same light in every direction.
+        let light_f = if s == 1 { Vec3::new(1.0, 1.0, 1.0) } else { light_end.f(-to_light, light_path[s - 2].p - light_end.p, time, mats) };
+        let camera_f = camera_end.f(camera_path[t - 2].p - camera_end.p, to_light, time, mats);
+        let geometry = camera_end.abs_cos(to_light) * light_end.abs_cos(to_light) / to_light.length_squared();
+        let radiance = geometry * (light_end.beta * light_f * camera_f * camera_end.beta);
+        if radiance.max_component() <= 0.0 || !visible(scene, camera_end.p, light_end.p, time, sampler) {
+            return zero;
+        }
+        return self.mis_weight(scene, light_path, camera_path, sampled_light.as_ref(), s, t, time, mats) * radiance;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn mis_weight(&self, scene: &Scene, light_path: &[Vertex], camera_path: &[Vertex], sampled: Option<&Vertex>, s: usize, t: usize, time: f64, mats: &[Arc<dyn Material>]) -> f64 {
+        // The weight of the strategy with s light and t camera vertices, from the ratios of the
+        // densities of the other strategies building the same path to its own. Moving the join
+        // one vertex along the path changes the density of that vertex only, from one subpath's
+        // to the other's, so the ratios are products over the vertices passed. The sampled vertex
+        // replaces the end of the light subpath when s is 1, or the lens vertex when t is 1.
+        if s + t == 2 {
+            return 1.0;
+        }
+        let camera_end = match sampled {
+            Some(vertex) if t == 1 => vertex,
+            _ => &camera_path[t - 1]
+        };
+        let light_end = match sampled {
+            Some(vertex) if s == 1 => Some(vertex),
+            _ if s > 0 => Some(&light_path[s - 1]),
+            _ => None
+        };
+        let camera_previous = if t > 1 { Some(&camera_path[t - 2]) } else { None };
+        let light_previous = if s > 1 { Some(&light_path[s - 2]) } else { None };
+
+        let mut camera: Vec<Densities> = camera_path[..t].iter().map(Vertex::densities).collect();
+        let mut light: Vec<Densities> = light_path[..s].iter().map(Vertex::densities).collect();
+        if let Some(light_end) = light_end {
+            light[s - 1] = light_end.densities();
+        }
+
+        // The densities across the join, as the other subpath would have picked the vertices.
+        match light_end {
+            Some(light_end) => {
+                camera[t - 1].rev = light_end.pdf(&self.camera, light_previous, camera_end, time, mats);
+                if let Some(camera_previous) = camera_previous {
+                    camera[t - 2].rev = camera_end.pdf(&self.camera, Some(light_end), camera_previous, time, mats);
+                }
+                light[s - 1].rev = camera_end.pdf(&self.camera, camera_previous, light_end, time, mats);
+                if let Some(light_previous) = light_previous {
+                    light[s - 2].rev = light_end.pdf(&self.camera, Some(camera_end), light_previous, time, mats);
+                }
+            }
+            None => {
+                // Lights that aren't sampled, such as those inside instances, are only ever found
+                // by the camera subpath.
+                camera[t - 1].rev = scene.lights.surface_pdf(camera_end.p, time);
+                if camera[t - 1].rev <= 0.0 {
+                    return 1.0;
+                }
+                if let Some(camera_previous) = camera_previous {
+                    camera[t - 2].rev = camera_end.emission_pdf(camera_previous);
+                }
+            }
+        }
+
+        // Specular vertices have no densities; theirs would cancel out of the ratios.
+        let remap = |pdf: f64| if pdf == 0.0 { 1.0 } else { pdf };
+        let mut sum = 0.0;
+        let mut ratio = 1.0;
+        for i in (1..t).rev() {
+            ratio *= remap(camera[i].rev) / remap(camera[i].fwd);
+            if !camera[i].delta && !camera[i - 1].delta {
+                sum += self.mis_heuristic.relative_weight(ratio);
+            }
+        }
+        ratio = 1.0;
+        for i in (0..s).rev() {
+            ratio *= remap(light[i].rev) / remap(light[i].fwd);
+            let previous_delta = i > 0 && light[i - 1].delta;
+            if !light[i].delta && !previous_delta {
+                sum += self.mis_heuristic.relative_weight(ratio);
+            }
+        }
+        return 1.0 / (1.0 + sum);
+    }
+}
+
+impl Vertex {
+    fn camera(p: Vec3) -> Self {
+        Self {
+            kind: VertexKind::Camera,
+            p,
+            normal: Vec3::new(0.0, 0.0, 0.0),
+            hit: None,
+            beta: Vec3::new(1.0, 1.0, 1.0),
+            delta: false,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0
+        }
+    }
+
+    fn light(sample: SurfaceSample, mats: &[Arc<dyn Material>]) -> Self {
+        let emitted = mats[sample.material as usize].emitted(sample.u, sample.v, sample.p);
+        Self {
+            kind: VertexKind::Light,
+            p: sample.p,
+            normal: sample.normal,
+            hit: None,
+            beta: emitted / sample.pdf,
+            delta: false,
+            pdf_fwd: sample.pdf,
+            pdf_rev: 0.0
+        }
+    }
+
+    fn scattering(hit: HitRecord, beta: Vec3, mats: &[Arc<dyn Material>]) -> Self {
+        let kind = if mats[hit.material as usize].is_volume() { VertexKind::Medium } else { VertexKind::Surface };
+        Self {
+            kind,
+            p: hit.p,
+            normal: hit.normal,
+            hit: Some(hit),
+            beta,
+            delta: false,
+            pdf_fwd: 0.0,
+            pdf_rev: 0.0
+        }
+    }
+
+    fn densities(&self) -> Densities {
+        return Densities { fwd: self.pdf_fwd, rev: self.pdf_rev, delta: self.delta };
+    }
+
+    fn abs_cos(&self, direction: Vec3) -> f64 {
+        // The cosine of the direction to the normal; the lens and points in media take light from
+        // every direction alike.
+        if self.kind != VertexKind::Surface && self.kind != VertexKind::Light {
+            return 1.0;
+        }
+        return vec3::dot(&self.normal, &direction.normalized()).abs();
+    }
+
+    fn area_density(&self, pdf: f64, next: &Vertex) -> f64 {
+        // A density over solid angle of directions from the vertex, as a density over area at next.
+        let to_next = next.p - self.p;
+        return pdf * next.abs_cos(to_next) / to_next.length_squared();
+    }
+
+    fn local_hit(&self, wo: Vec3, time: f64) -> Option<(Ray, HitRecord)> {
+        // A ray arriving at the vertex from direction wo, and its hit, with the normal facing it.
+        let hit = self.hit.as_ref()?;
+        let ray = Ray::new2(self.p + wo, -wo, time);
+        let material = hit.material;
+        let hit_record = if self.kind == VertexKind::Medium {
+            HitRecord::new2(hit.p, hit.t, hit.u, hit.v, material)
+        } else {
+            let outward_normal = if hit.front_face { hit.normal } else { -hit.normal };
+            HitRecord::new(&ray, hit.p, outward_normal, hit.t, hit.u, hit.v, material)
+        };
+        return Some((ray, hit_record));
+    }
+
+    fn f(&self, wo: Vec3, wi: Vec3, time: f64, mats: &[Arc<dyn Material>]) -> Vec3 {
+        // The BSDF, or the phase function in media, for light arriving from direction wi and
+        // leaving towards wo.
+        let cosine = self.abs_cos(wi);
+        let Some((ray, hit)) = self.local_hit(wo, time) else {
+            return Vec3::new(0.0, 0.0, 0.0);
+        };
+        if cosine <= 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        return mats[hit.material as usize].eval(&ray, &hit, wi) / cosine;
+    }
+
+    fn scattering_pdf(&self, wo: Vec3, wi: Vec3, time: f64, mats: &[Arc<dyn Material>]) -> f64 {
+        // Density over solid angle of scattering a path that arrived from direction wo towards wi.
+        let Some((ray, hit)) = self.local_hit(wo, time) else {
+            return 0.0;
+        };
+        return mats[hit.material as usize].scattering_pdf(&ray, &hit, wi);
+    }
+
+    fn emission_pdf(&self, next: &Vertex) -> f64 {
+        // Density over area at next of light leaving the vertex, on a light, towards it. Light
+        // subpaths leave from either side of the light, in a cosine weighted direction.
+        let direction = next.p - self.p;
+        return self.area_density(self.abs_cos(direction) / (2.0 * PI), next);
+    }
+
+    fn pdf(&self, camera: &Camera, previous: Option<&Vertex>, next: &Vertex, time: f64, mats: &[Arc<dyn Material>]) -> f64 {
+        // Density over area at next of the vertex picking it, when reached from previous.
+        return match self.kind {
+            VertexKind::Camera => self.area_density(camera.direction_pdf(self.p, next.p - self.p), next),
+            VertexKind::Light => self.emission_pdf(next),
+            VertexKind::Surface | VertexKind::Medium => match previous {
+                Some(previous) => self.area_density(self.scattering_pdf(previous.p - self.p, next.p - self.p, time, mats), next),
+                None => 0.0
+            }
+        };
+    }
+}
+
+impl Integrator for BdptIntegrator {
+    fn radiance(&self, ray: Ray, scene: &Scene, sampler: &mut dyn Sampler, splats: &mut Vec<Splat>, mats: &[Arc<dyn Material>]) -> Vec3 {
+        // Traces both subpaths at the ray's time, then tries every join that gives a path of up
+        // to max_depth segments. Joining the light subpath's first vertex straight to the lens is
+        // left out, as the camera subpath already sees the lights.
+        let time = ray.tm;
+        let max_depth = self.max_depth as usize;
+
+        let mut camera_path = vec![Vertex::camera(ray.origin)];
+        let pdf = self.camera.direction_pdf(ray.origin, ray.direction);
+        let mut radiance = self.random_walk(ray, Vec3::new(1.0, 1.0, 1.0), pdf, true, max_depth + 1, scene, sampler, &mut camera_path, mats);
+
+        let mut light_path: Vec<Vertex> = Vec::new();
+        if let Some(sample) = scene.lights.sample_surface(time, sampler) {
+            let light = Vertex::light(sample, mats);
+            // Either side of the light, with a cosine weighted direction.
+            let side = if sampler.get_1d() < 0.5 { light.normal } else { -light.normal };
+            let direction = Onb::new(side).transform(sample_cosine_direction(sampler.get_2d()));
+            let pdf = light.abs_cos(direction) / (2.0 * PI);
+            let beta = (light.abs_cos(direction) / pdf) * light.beta;
+            let light_ray = Ray::new2(light.p, direction, time);
+            light_path.push(light);
+            if max_depth > 1 && pdf > 0.0 && beta.max_component() > 0.0 {
+                self.random_walk(light_ray, beta, pdf, false, max_depth, scene, sampler, &mut light_path, mats);
+            }
+        }
+
+        for t in 1..=camera_path.len() {
+            for s in 0..=light_path.len() {
+                if s + t < 2 || (s == 1 && t == 1) || s + t - 1 > max_depth {
+                    continue;
+                }
+                radiance = radiance + self.connect(scene, &light_path, &camera_path, s, t, time, sampler, splats, mats);
+            }
+        }
+        return radiance;
+    }
+}
+
+fn visible(scene: &Scene, from: Vec3, to: Vec3, time: f64, sampler: &mut dyn Sampler) -> bool {
+    // Whether nothing is in the way between the points. Media in between stop the shadow ray where
+    // they would scatter it, so it gets through as often as light does.
+    let direction = to - from;
+    let distance = direction.length();
+    let shadow_ray = Ray::new2(from, direction / distance, time);
+    return scene.world.hit(&shadow_ray, &Interval::new(0.0001, distance - 0.0001), sampler).is_none();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::WorkingSpace;
+    use crate::filter::{Filter, FilterKind};
+    use crate::integrators::PathIntegrator;
+    use crate::rtweekend::Rng;
+    use crate::scenes;
+
+    #[test]
+    fn converges_to_the_path_traced_image() {
+        // With a wide filter, so that light splatted from the light paths is spread like the
+        // camera samples, up to the edges of the image.
+        let (builder, scene, mats) = scenes::simple_light(&mut Rng::new(1), WorkingSpace::Rec709);
+        let camera = builder.image_width(32).samples_per_pixel(256).filter(Filter::new(FilterKind::Gaussian, 1.5)).build();
+        let render = |integrator: &dyn Integrator| {
+            let mut film = camera.new_film(false);
+            camera.render(&scene, integrator, &mut film, camera.samples_per_pixel(), None, &mats);
+            return film.pixels().iter().map(|pixel| pixel.x + pixel.y + pixel.z).collect::<Vec<f64>>();
+        };
+        let path = render(&PathIntegrator::new(6, 3, MisHeuristic::Power));
+        let bdpt = render(&BdptIntegrator::new(6, 3, MisHeuristic::Power, camera));
+
+        // Single pixels are too noisy to compare, so compare the sums of the rows and columns.
+        let (width, height) = (32, camera.image_height() as usize);
+        let lines = (0..height).map(|y| (0..width).map(|x| y * width + x).collect::<Vec<usize>>())
+            .chain((0..width).map(|x| (0..height).map(|y| y * width + x).collect()));
+        for (line, pixels) in lines.enumerate() {
+            let path_sum: f64 = pixels.iter().map(|&index| path[index]).sum();
+            let bdpt_sum: f64 = pixels.iter().map(|&index| bdpt[index]).sum();
+            assert!((bdpt_sum - path_sum).abs() <= 0.05 * path_sum + 0.01, "line {}: {} against {}", line, bdpt_sum, path_sum);
+        }
+    }
+}
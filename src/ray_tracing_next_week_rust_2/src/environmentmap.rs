@@ -0,0 +1,97 @@
+use std::f64::consts::PI;
+
+use crate::background::{Background, BackgroundSample};
+use crate::distribution::Distribution2D;
+use crate::color::WorkingSpace;
+use crate::imagetexture::ImageTexture;
+use crate::rtweekend::degrees_to_radians;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+
+// Light from an equirectangular (latitude-longitude) image wrapped around the scene: the image's
+// top row is straight up, and its columns go once around the vertical axis. Directions are
+// sampled in proportion to the brightness of the texels, so small bright areas such as the sun
+// are found without waiting for scattered rays to hit them.
+pub struct EnvironmentMap {
+    texture: ImageTexture,
+    sin_rotation: f64,
+    cos_rotation: f64,
+    intensity: f64,
+    distribution: Distribution2D
+}
+
+impl EnvironmentMap {
+    pub fn new(path: &str, rotation: f64, intensity: f64, space: WorkingSpace) -> Result<Self, String> {
+        // The map is turned by rotation degrees about the vertical axis, and its radiance scaled
+        // by intensity.
+        let texture = ImageTexture::new(path, space)?;
+        let (width, height) = (texture.width(), texture.height());
+
+        // Rows near the poles cover less solid angle than rows at the horizon.
+        let mut weights = Vec::with_capacity(width * height);
+        for j in 0..height {
+            let sin_theta = (PI * (j as f64 + 0.5) / height as f64).sin();
+            for i in 0..width {
+                let texel = texture.texel(i, j);
+                weights.push((texel.x + texel.y + texel.z).max(0.0) * sin_theta);
+            }
+        }
+        let distribution = Distribution2D::new(&weights, width);
+
+        let radians = degrees_to_radians(rotation);
+        return Ok(Self {
+            texture, sin_rotation: radians.sin(), cos_rotation: radians.cos(), intensity, distribution
+        });
+    }
+
+    fn map_point(&self, direction: Vec3) -> (f64, f64) {
+        // The image coordinates in [0, 1)^2 of the unit direction, counting from the top left.
+        let x = self.cos_rotation * direction.x - self.sin_rotation * direction.z;
+        let z = self.sin_rotation * direction.x + self.cos_rotation * direction.z;
+        let theta = direction.y.clamp(-1.0, 1.0).acos();
+        let phi = (-z).atan2(x) + PI;
+        return ((phi / (2.0 * PI)).min(1.0 - f64::EPSILON / 2.0), (theta / PI).min(1.0 - f64::EPSILON / 2.0));
+    }
+
+    fn map_direction(&self, point: (f64, f64)) -> Vec3 {
+        let phi = 2.0 * PI * point.0 - PI;
+        let theta = PI * point.1;
+        let (x, y, z) = (theta.sin() * phi.cos(), theta.cos(), -theta.sin() * phi.sin());
+        return Vec3::new(
+            self.cos_rotation * x + self.sin_rotation * z,
+            y,
+            -self.sin_rotation * x + self.cos_rotation * z);
+    }
+
+    fn texel_at(&self, point: (f64, f64)) -> Vec3 {
+        let i = ((point.0 * self.texture.width() as f64) as usize).min(self.texture.width() - 1);
+        let j = ((point.1 * self.texture.height() as f64) as usize).min(self.texture.height() - 1);
+        return self.intensity * self.texture.texel(i, j);
+    }
+}
+
+impl Background for EnvironmentMap {
+    fn color(&self, direction: Vec3) -> Vec3 {
+        return self.texel_at(self.map_point(direction));
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<BackgroundSample> {
+        let (point, map_pdf) = self.distribution.sample_continuous(sampler.get_2d());
+        let sin_theta = (PI * point.1).sin();
+        if map_pdf <= 0.0 || sin_theta <= 0.0 {
+            return None;
+        }
+        // The map covers 2 pi by pi radians, squeezed by sin theta towards the poles.
+        let pdf = map_pdf / (2.0 * PI * PI * sin_theta);
+        return Some(BackgroundSample::new(self.map_direction(point), self.texel_at(point), pdf));
+    }
+
+    fn pdf(&self, direction: Vec3) -> f64 {
+        let point = self.map_point(direction);
+        let sin_theta = (PI * point.1).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        return self.distribution.pdf(point) / (2.0 * PI * PI * sin_theta);
+    }
+}
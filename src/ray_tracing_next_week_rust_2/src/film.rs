@@ -0,0 +1,507 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+
+use crate::vec3::Vec3;
+use crate::aov::AovSample;
+use crate::tiles::Tile;
+use crate::filter::Filter;
+use crate::color::WorkingSpace;
+use crate::tonemap;
+
+const CHECKPOINT_MAGIC: &str = "RTCHECKPOINT 4";
+const MIN_ERROR_SCALE: f64 = 1.0 / 256.0; // Errors of darker pixels are measured against this
+const PIXEL_BYTES: u64 = 7 * 8; // Checkpoint bytes of a pixel's sums and statistics
+const AOV_BYTES: u64 = 14 * 8; // Checkpoint bytes of a pixel's output variables
+
+// The render target: per-pixel filter-weighted sums of linear radiance samples in the working
+// color space, with the sums of their weights, statistics of the samples taken in each pixel, and
+// optionally the sums of the first-hit output variables. Pixels are stored row by row from the
+// top left.
+// The samples per pixel the render's sampler spreads its values over are kept with the film, so
+// a resumed render goes on with the same strata whatever its --spp.
+// Light splatted by paths from the lights is held apart during a pass, with the weights the pass
+// adds to each pixel and the number of light paths, until the pass is done.
+pub struct Film {
+    width: u64,
+    height: u64,
+    sums: Vec<Vec3>,
+    weights: Vec<f64>,
+    stats: Vec<PixelStats>,
+    aovs: Option<Vec<AovSample>>,
+    sampler_samples: u64,
+    pass_splats: Vec<Vec3>,
+    pass_weights: Vec<f64>,
+    pass_light_paths: u64
+}
+
+// The samples of one tile, splatted through the reconstruction filter into the pixels they reach,
+// which may be outside the tile, before they are added to the film.
+pub struct FilmTile {
+    tile: Tile,              // The pixels the samples were taken in
+    bounds: Tile,            // The pixels the samples reach
+    stats: Vec<PixelStats>,  // Statistics of the samples taken in each pixel of the tile
+    sums: Vec<Vec3>,
+    weights: Vec<f64>,
+    splats: HashMap<(u64, u64), Vec3> // Light splatted into each pixel by the tile's light paths
+}
+
+// The number of samples taken in a pixel, with the running mean and sum of squared differences
+// from the mean of their luminance, from which their variance is estimated.
+#[derive(Copy, Clone, Debug)]
+struct PixelStats {
+    count: u64,
+    mean: f64,
+    m2: f64
+}
+
+impl Film {
+    pub fn new(width: u64, height: u64, with_aovs: bool, sampler_samples: u64) -> Self {
+        let pixel_count = (width * height) as usize;
+        Self {
+            width,
+            height,
+            sums: vec![Vec3::new(0.0, 0.0, 0.0); pixel_count],
+            weights: vec![0.0; pixel_count],
+            stats: vec![PixelStats::new(); pixel_count],
+            aovs: if with_aovs { Some(vec![AovSample::miss(); pixel_count]) } else { None },
+            sampler_samples,
+            pass_splats: vec![Vec3::new(0.0, 0.0, 0.0); pixel_count],
+            pass_weights: vec![0.0; pixel_count],
+            pass_light_paths: 0
+        }
+    }
+
+    pub fn width(&self) -> u64 {
+        return self.width;
+    }
+
+    pub fn height(&self) -> u64 {
+        return self.height;
+    }
+
+    pub fn has_aovs(&self) -> bool {
+        return self.aovs.is_some();
+    }
+
+    pub fn sampler_samples(&self) -> u64 {
+        return self.sampler_samples;
+    }
+
+    pub fn sample_count(&self, x: u64, y: u64) -> u64 {
+        return self.stats[self.index(x, y)].count;
+    }
+
+    pub fn min_sample_count(&self, region: &Tile) -> u64 {
+        // The number of samples every pixel in the region has had.
+        return region.pixels().map(|(x, y)| self.sample_count(x, y)).min().unwrap_or(0);
+    }
+
+    pub fn relative_error(&self, x: u64, y: u64) -> f64 {
+        // The estimated standard error of the pixel's mean luminance, relative to the mean, or to
+        // an 8-bit display step for darker pixels. Infinite until there are two samples.
+        let stats = &self.stats[self.index(x, y)];
+        if stats.count < 2 {
+            return f64::INFINITY;
+        }
+        let variance = stats.m2 / (stats.count - 1) as f64;
+        return (variance / stats.count as f64).sqrt() / stats.mean.max(MIN_ERROR_SCALE);
+    }
+
+    pub fn sample_counts(&self) -> Vec<u64> {
+        return self.stats.iter().map(|stats| stats.count).collect();
+    }
+
+    pub fn cropped(&self, region: &Tile) -> Film {
+        // A copy of the region of the film.
+        let mut film = Film::new(region.x1 - region.x0, region.y1 - region.y0, self.aovs.is_some(), self.sampler_samples);
+        for (index, (x, y)) in region.pixels().enumerate() {
+            let source = self.index(x, y);
+            film.sums[index] = self.sums[source];
+            film.weights[index] = self.weights[source];
+            film.stats[index] = self.stats[source];
+            if let (Some(aovs), Some(source_aovs)) = (film.aovs.as_mut(), self.aovs.as_ref()) {
+                aovs[index] = source_aovs[source];
+            }
+        }
+        return film;
+    }
+
+    pub fn add_tile(&mut self, film_tile: &FilmTile) {
+        // Adds a tile's splatted samples, and the statistics of its pixels. Each sample also
+        // counts as a light path, whether or not the integrator traced one.
+        for (index, (x, y)) in film_tile.bounds.pixels().enumerate() {
+            let film_index = self.index(x, y);
+            self.sums[film_index] = self.sums[film_index] + film_tile.sums[index];
+            self.weights[film_index] += film_tile.weights[index];
+            self.pass_weights[film_index] += film_tile.weights[index];
+        }
+        for (index, (x, y)) in film_tile.tile.pixels().enumerate() {
+            let film_index = self.index(x, y);
+            self.stats[film_index] = self.stats[film_index].merge(&film_tile.stats[index]);
+        }
+        for (&(x, y), radiance) in film_tile.splats.iter() {
+            let film_index = self.index(x, y);
+            self.pass_splats[film_index] = self.pass_splats[film_index] + *radiance;
+        }
+        self.pass_light_paths += film_tile.sample_count();
+    }
+
+    pub fn finish_pass(&mut self) {
+        // Adds the light splatted over the pass to the pixels sampled in it. The mean over the
+        // pass's light paths is that part of the pass's estimate of the pixel, so it's weighted
+        // like the pixel's samples in the pass, and the pixel stays the weighted mean of whole
+        // estimates however passes, crop windows and adaptive sampling split up the render.
+        if self.pass_light_paths > 0 {
+            let scale = 1.0 / self.pass_light_paths as f64;
+            for index in 0..self.sums.len() {
+                self.sums[index] = self.sums[index] + (self.pass_weights[index] * scale) * self.pass_splats[index];
+            }
+        }
+        self.pass_splats.fill(Vec3::new(0.0, 0.0, 0.0));
+        self.pass_weights.fill(0.0);
+        self.pass_light_paths = 0;
+    }
+
+    pub fn add_aov_samples(&mut self, x: u64, y: u64, sum: &AovSample) {
+        // Adds summed first-hit values to a pixel.
+        let index = self.index(x, y);
+        if let Some(aovs) = self.aovs.as_mut() {
+            aovs[index] = aovs[index].accumulate(sum);
+        }
+    }
+
+    pub fn pixels(&self) -> Vec<Vec3> {
+        // The filter-weighted mean radiance of each pixel, black where there are no samples.
+        return self.sums.iter().zip(self.weights.iter())
+            .map(|(sum, weight)| if *weight == 0.0 { Vec3::new(0.0, 0.0, 0.0) } else { *sum / *weight })
+            .collect();
+    }
+
+    pub fn aov_samples(&self) -> Vec<AovSample> {
+        // The first-hit values averaged over each pixel's samples; empty if they weren't gathered.
+        let Some(aovs) = self.aovs.as_ref() else {
+            return Vec::new();
+        };
+        return aovs.iter().zip(self.stats.iter())
+            .map(|(sum, stats)| if stats.count == 0 { *sum } else { sum.scaled(1.0 / stats.count as f64) })
+            .collect();
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        // Saves the raw sums and sample statistics so the render can be resumed: a text header
+        // with the size, whether output variables follow and the sampler's samples per pixel,
+        // then little endian f64 sums and weights, u64 count and f64 luminance mean and squared
+        // differences per pixel, then the output variables if gathered.
+        // It's written to a temporary file first, so an interrupted save leaves the previous
+        // checkpoint intact.
+        let temp_path = format!("{}.tmp", path);
+        let mut out = BufWriter::new(File::create(&temp_path)?);
+        write!(out, "{}\n{} {} {} {}\n", CHECKPOINT_MAGIC, self.width, self.height, self.aovs.is_some() as u8, self.sampler_samples)?;
+        for index in 0..self.sums.len() {
+            write_vec3(&mut out, &self.sums[index])?;
+            out.write_all(&self.weights[index].to_le_bytes())?;
+            out.write_all(&self.stats[index].count.to_le_bytes())?;
+            out.write_all(&self.stats[index].mean.to_le_bytes())?;
+            out.write_all(&self.stats[index].m2.to_le_bytes())?;
+        }
+        if let Some(aovs) = self.aovs.as_ref() {
+            for aov in aovs.iter() {
+                write_vec3(&mut out, &aov.normal)?;
+                out.write_all(&aov.depth.to_le_bytes())?;
+                write_vec3(&mut out, &aov.albedo)?;
+                write_vec3(&mut out, &aov.position)?;
+                write_vec3(&mut out, &aov.uv)?;
+                out.write_all(&aov.material.map_or(-1, |material| material as i64).to_le_bytes())?;
+            }
+        }
+        out.flush()?;
+        drop(out);
+        return fs::rename(temp_path, path);
+    }
+
+    pub fn load(path: &str, width: u64, height: u64) -> io::Result<Self> {
+        // Loads a checkpoint saved by a width x height render. The size in the header is checked
+        // against the render's and the file's before anything is allocated.
+        let file = File::open(path)?;
+        let file_length = file.metadata()?.len();
+        let mut input = BufReader::new(file);
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        let mut line = String::new();
+        let mut header_length = input.read_line(&mut line)? as u64;
+        if line.trim_end() != CHECKPOINT_MAGIC {
+            if line.starts_with("RTCHECKPOINT") {
+                return Err(invalid("checkpoint written by an older version of the renderer"));
+            }
+            return Err(invalid("not a checkpoint file"));
+        }
+        line.clear();
+        header_length += input.read_line(&mut line)? as u64;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 4 {
+            return Err(invalid(&format!("the checkpoint header has {} fields instead of 4", fields.len())));
+        }
+        let parse_field = |index: usize, name: &str| {
+            return fields[index].parse::<u64>()
+                .map_err(|_| invalid(&format!("bad {} '{}' in the checkpoint header", name, fields[index])));
+        };
+        let saved_width = parse_field(0, "width")?;
+        let saved_height = parse_field(1, "height")?;
+        let with_aovs = parse_field(2, "output variables flag")?;
+        let sampler_samples = parse_field(3, "sampler samples per pixel")?;
+        if with_aovs > 1 {
+            return Err(invalid(&format!("bad output variables flag '{}' in the checkpoint header", with_aovs)));
+        }
+        if sampler_samples == 0 {
+            return Err(invalid("bad sampler samples per pixel '0' in the checkpoint header"));
+        }
+        if saved_width != width || saved_height != height {
+            return Err(invalid(&format!("the checkpoint is {}x{} but the render is {}x{}", saved_width, saved_height, width, height)));
+        }
+        let pixel_length = if with_aovs != 0 { PIXEL_BYTES + AOV_BYTES } else { PIXEL_BYTES };
+        let expected_length = width.checked_mul(height)
+            .and_then(|pixel_count| pixel_count.checked_mul(pixel_length))
+            .and_then(|data_length| data_length.checked_add(header_length));
+        if expected_length != Some(file_length) {
+            return Err(invalid("the checkpoint's length doesn't match its size"));
+        }
+
+        let mut film = Film::new(width, height, with_aovs != 0, sampler_samples);
+        for index in 0..film.sums.len() {
+            film.sums[index] = read_vec3(&mut input)?;
+            film.weights[index] = f64::from_le_bytes(read_bytes(&mut input)?);
+            film.stats[index] = PixelStats {
+                count: u64::from_le_bytes(read_bytes(&mut input)?),
+                mean: f64::from_le_bytes(read_bytes(&mut input)?),
+                m2: f64::from_le_bytes(read_bytes(&mut input)?)
+            };
+        }
+        if let Some(aovs) = film.aovs.as_mut() {
+            for aov in aovs.iter_mut() {
+                aov.normal = read_vec3(&mut input)?;
+                aov.depth = f64::from_le_bytes(read_bytes(&mut input)?);
+                aov.albedo = read_vec3(&mut input)?;
+                aov.position = read_vec3(&mut input)?;
+                aov.uv = read_vec3(&mut input)?;
+                let material = i64::from_le_bytes(read_bytes(&mut input)?);
+                aov.material = if material < 0 { None } else { Some(material as usize) };
+            }
+        }
+        return Ok(film);
+    }
+
+    fn index(&self, x: u64, y: u64) -> usize {
+        return (y * self.width + x) as usize;
+    }
+}
+
+impl FilmTile {
+    pub fn new(tile: Tile, bounds: Tile) -> Self {
+        let pixel_count = bounds.pixel_count() as usize;
+        Self {
+            tile,
+            bounds,
+            stats: vec![PixelStats::new(); tile.pixel_count() as usize],
+            sums: vec![Vec3::new(0.0, 0.0, 0.0); pixel_count],
+            weights: vec![0.0; pixel_count],
+            splats: HashMap::new()
+        }
+    }
+
+    pub fn sample_count(&self) -> u64 {
+        // The number of samples taken in the tile.
+        return self.stats.iter().map(|stats| stats.count).sum();
+    }
+
+    pub fn add_sample(&mut self, pixel_index: usize, x: f64, y: f64, radiance: Vec3, filter: &Filter, space: WorkingSpace) {
+        // Splats a sample taken in the tile's pixel_index-th pixel, at image position x, y with
+        // pixel centers at whole numbers, into the pixels within the filter radius. The radiance
+        // is in the given working space.
+        self.stats[pixel_index].add(tonemap::luminance(space.working_to_rec709(radiance)));
+        let radius = filter.radius();
+        let x0 = ((x - radius).ceil().max(self.bounds.x0 as f64)) as u64;
+        let y0 = ((y - radius).ceil().max(self.bounds.y0 as f64)) as u64;
+        let x1 = ((x + radius).floor() + 1.0).min(self.bounds.x1 as f64) as u64;
+        let y1 = ((y + radius).floor() + 1.0).min(self.bounds.y1 as f64) as u64;
+        let width = self.bounds.x1 - self.bounds.x0;
+        let column_weights: Vec<f64> = (x0..x1).map(|px| filter.evaluate_1d(px as f64 - x)).collect();
+        for py in y0..y1 {
+            let row_weight = filter.evaluate_1d(py as f64 - y);
+            for px in x0..x1 {
+                let weight = row_weight * column_weights[(px - x0) as usize];
+                let index = ((py - self.bounds.y0) * width + px - self.bounds.x0) as usize;
+                self.sums[index] = self.sums[index] + weight * radiance;
+                self.weights[index] += weight;
+            }
+        }
+    }
+
+    pub fn add_splat(&mut self, x: u64, y: u64, radiance: Vec3) {
+        // Adds light carried to pixel x, y of the image, which may be outside the tile, by one of
+        // the tile's light paths.
+        let sum = self.splats.entry((x, y)).or_insert(Vec3::new(0.0, 0.0, 0.0));
+        *sum = *sum + radiance;
+    }
+}
+
+impl PixelStats {
+    fn new() -> Self {
+        Self {
+            count: 0, mean: 0.0, m2: 0.0
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        // Welford's update.
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn merge(&self, other: &PixelStats) -> PixelStats {
+        // Chan et al.'s combination of the statistics of two sets of samples.
+        let count = self.count + other.count;
+        if count == 0 {
+            return *self;
+        }
+        let delta = other.mean - self.mean;
+        let other_fraction = other.count as f64 / count as f64;
+        return PixelStats {
+            count,
+            mean: self.mean + delta * other_fraction,
+            m2: self.m2 + other.m2 + delta * delta * self.count as f64 * other_fraction
+        };
+    }
+}
+
+fn write_vec3(out: &mut dyn Write, v: &Vec3) -> io::Result<()> {
+    out.write_all(&v.x.to_le_bytes())?;
+    out.write_all(&v.y.to_le_bytes())?;
+    return out.write_all(&v.z.to_le_bytes());
+}
+
+fn read_bytes(input: &mut dyn Read) -> io::Result<[u8; 8]> {
+    let mut bytes = [0_u8; 8];
+    input.read_exact(&mut bytes)?;
+    return Ok(bytes);
+}
+
+fn read_vec3(input: &mut dyn Read) -> io::Result<Vec3> {
+    let x = f64::from_le_bytes(read_bytes(input)?);
+    let y = f64::from_le_bytes(read_bytes(input)?);
+    let z = f64::from_le_bytes(read_bytes(input)?);
+    return Ok(Vec3::new(x, y, z));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        return std::env::temp_dir().join(format!("film-{}-{}", std::process::id(), name)).to_string_lossy().to_string();
+    }
+
+    fn test_film() -> Film {
+        let mut film = Film::new(3, 2, true, 16);
+        for index in 0..film.sums.len() {
+            let value = index as f64;
+            film.sums[index] = Vec3::new(value, value + 0.25, -value);
+            film.weights[index] = 0.5 * value;
+            film.stats[index] = PixelStats { count: index as u64, mean: value / 3.0, m2: value * value };
+        }
+        if let Some(aovs) = film.aovs.as_mut() {
+            for (index, aov) in aovs.iter_mut().enumerate() {
+                let value = index as f64;
+                *aov = AovSample {
+                    normal: Vec3::new(0.0, 1.0, value),
+                    depth: 10.0 + value,
+                    albedo: Vec3::new(0.1, 0.2, value),
+                    position: Vec3::new(value, -value, 1.0),
+                    uv: Vec3::new(0.5, value, 0.0),
+                    material: if index == 4 { None } else { Some(index) }
+                };
+            }
+        }
+        return film;
+    }
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let path = temp_path("round-trip.ckpt");
+        let film = test_film();
+        film.save(&path).unwrap();
+        let loaded = Film::load(&path, 3, 2).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.sums, film.sums);
+        assert_eq!(loaded.weights, film.weights);
+        for (a, b) in loaded.stats.iter().zip(film.stats.iter()) {
+            assert_eq!((a.count, a.mean, a.m2), (b.count, b.mean, b.m2));
+        }
+        assert_eq!(loaded.aovs, film.aovs);
+        assert_eq!(loaded.sampler_samples(), 16);
+        assert_eq!(loaded.aovs.unwrap()[4].material, None);
+    }
+
+    #[test]
+    fn checkpoint_without_aovs_round_trip() {
+        let path = temp_path("no-aovs.ckpt");
+        let mut film = test_film();
+        film.aovs = None;
+        film.save(&path).unwrap();
+        let loaded = Film::load(&path, 3, 2).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(!loaded.has_aovs());
+        assert_eq!(loaded.sums, film.sums);
+    }
+
+    #[test]
+    fn load_rejects_other_resolutions() {
+        let path = temp_path("resolution.ckpt");
+        test_film().save(&path).unwrap();
+        let result = Film::load(&path, 2, 3);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.err().map(|err| err.kind()), Some(io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn load_rejects_huge_or_truncated_checkpoints() {
+        // A header claiming a huge image must fail on the file's length, not try to allocate it.
+        let path = temp_path("huge.ckpt");
+        fs::write(&path, format!("{}\n{} {} 1 16\n", CHECKPOINT_MAGIC, 1_u64 << 31, 1_u64 << 31)).unwrap();
+        let huge = Film::load(&path, 1 << 31, 1 << 31);
+
+        test_film().save(&path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 8);
+        fs::write(&path, &bytes).unwrap();
+        let truncated = Film::load(&path, 3, 2);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(huge.err().map(|err| err.kind()), Some(io::ErrorKind::InvalidData));
+        assert_eq!(truncated.err().map(|err| err.kind()), Some(io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn load_names_the_bad_header_field() {
+        let path = temp_path("header.ckpt");
+        let mut errors = Vec::new();
+        for header in ["3 2 1", "3 2 1 16 5", "3 x 1 16", "3 2 yes 16", "3 2 2 16", "3 2 1 -4", "3 2 1 0"] {
+            fs::write(&path, format!("{}\n{}\n", CHECKPOINT_MAGIC, header)).unwrap();
+            errors.push(Film::load(&path, 3, 2).err().map(|err| err.to_string()).unwrap_or_default());
+        }
+        fs::remove_file(&path).unwrap();
+
+        assert!(errors[0].contains("3 fields"), "{}", errors[0]);
+        assert!(errors[1].contains("5 fields"), "{}", errors[1]);
+        assert!(errors[2].contains("bad height 'x'"), "{}", errors[2]);
+        assert!(errors[3].contains("bad output variables flag 'yes'"), "{}", errors[3]);
+        assert!(errors[4].contains("bad output variables flag '2'"), "{}", errors[4]);
+        assert!(errors[5].contains("bad sampler samples per pixel '-4'"), "{}", errors[5]);
+        assert!(errors[6].contains("bad sampler samples per pixel '0'"), "{}", errors[6]);
+    }
+}
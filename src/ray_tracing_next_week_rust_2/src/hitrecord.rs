@@ -1,9 +1,6 @@
-use std::sync::Arc;
-
 use crate::vec3;
 use crate::vec3::Vec3;
 use crate::ray::Ray;
-use crate::material::Material;
 
 // #[derive(Copy, Clone, Debug)]
 pub struct HitRecord {
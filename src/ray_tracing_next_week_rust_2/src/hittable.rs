@@ -2,9 +2,53 @@ use crate::ray::Ray;
 use crate::interval::Interval;
 use crate::hitrecord::HitRecord;
 use crate::aabb::AABB;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+use crate::material::Material;
+use std::sync::Arc;
+
+// A point picked on the surface of an emissive hittable, for light paths to start from.
+pub struct SurfaceSample {
+    pub p: Vec3,
+    pub normal: Vec3, // Unit outward normal
+    pub u: f64,       // Texture coordinates
+    pub v: f64,
+    pub material: i16,
+    pub pdf: f64      // Probability density over area of picking the point
+}
 
 pub trait Hittable : Send + Sync {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord>;
+    // The sampler is for hittables that aren't solid, such as participating media.
+    fn hit(&self, ray: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord>;
 
     fn bounding_box(&self) -> AABB;
+
+    // Whether the hittable gives off light, so that it is sampled for direct lighting.
+    fn is_emissive(&self, _mats: &[Arc<dyn Material>]) -> bool {
+        return false;
+    }
+
+    // Probability density over solid angle of random picking the direction from origin, at the
+    // given time. Only emissive hittables and groups of them need to implement it.
+    fn pdf_value(&self, _origin: Vec3, _direction: Vec3, _time: f64) -> f64 {
+        return 0.0;
+    }
+
+    // A direction from origin towards a point picked on the hittable, at the given time; not
+    // necessarily of unit length.
+    fn random(&self, _origin: Vec3, _time: f64, _sampler: &mut dyn Sampler) -> Vec3 {
+        return Vec3::new(1.0, 0.0, 0.0);
+    }
+
+    // A point picked uniformly over the surface at the given time. Only emissive hittables and
+    // groups of them need to implement it.
+    fn sample_surface(&self, _time: f64, _sampler: &mut dyn Sampler) -> Option<SurfaceSample> {
+        return None;
+    }
+
+    // Probability density over area of sample_surface picking p, at the given time; 0 if p isn't
+    // on the surface.
+    fn surface_pdf(&self, _p: Vec3, _time: f64) -> f64 {
+        return 0.0;
+    }
 }
\ No newline at end of file
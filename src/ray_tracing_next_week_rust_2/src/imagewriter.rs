@@ -0,0 +1,464 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::vec3::Vec3;
+use crate::interval::Interval;
+use crate::tonemap::ToneMapper;
+use crate::film::Film;
+use crate::aov::Aov;
+use crate::color::{self, WorkingSpace};
+
+pub trait ImageWriter {
+    // Whether the format stores linear floating point radiance, in the working color space. Other
+    // formats are given sRGB encoded values, nominally in [0, 1].
+    fn is_hdr(&self) -> bool {
+        return false;
+    }
+
+    // Writes a width x height image, stored row by row from the top left. Formats that record
+    // their primaries are given the working space of the pixels.
+    fn write(&self, out: &mut dyn Write, width: u64, height: u64, pixels: &[Vec3], space: WorkingSpace) -> io::Result<()>;
+}
+
+// Binary portable pixmap (P6).
+pub struct PpmWriter;
+
+// PNG, 8 bits per channel, encoded in-process.
+pub struct PngWriter;
+
+// Portable float map, 32-bit float RGB.
+pub struct PfmWriter;
+
+// Radiance RGBE, run-length encoded.
+pub struct HdrWriter;
+
+// Single-part scanline OpenEXR with uncompressed 32-bit float R, G and B channels.
+pub struct ExrWriter;
+
+impl ImageWriter for PpmWriter {
+    fn write(&self, out: &mut dyn Write, width: u64, height: u64, pixels: &[Vec3], _space: WorkingSpace) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", width, height)?;
+        return out.write_all(&to_rgb_bytes(pixels));
+    }
+}
+
+impl ImageWriter for PngWriter {
+    fn write(&self, out: &mut dyn Write, width: u64, height: u64, pixels: &[Vec3], _space: WorkingSpace) -> io::Result<()> {
+        let png = lodepng::encode24(&to_rgb_bytes(pixels), width as usize, height as usize)
+            .map_err(|err| io::Error::other(err.to_string()))?;
+        return out.write_all(&png);
+    }
+}
+
+impl ImageWriter for PfmWriter {
+    fn is_hdr(&self) -> bool {
+        return true;
+    }
+
+    fn write(&self, out: &mut dyn Write, width: u64, height: u64, pixels: &[Vec3], _space: WorkingSpace) -> io::Result<()> {
+        // A negative scale marks little endian data. Rows are stored from the bottom up.
+        write!(out, "PF\n{} {}\n-1.0\n", width, height)?;
+        for row in pixels.chunks(width as usize).rev() {
+            for pixel in row.iter() {
+                out.write_all(&(pixel.x as f32).to_le_bytes())?;
+                out.write_all(&(pixel.y as f32).to_le_bytes())?;
+                out.write_all(&(pixel.z as f32).to_le_bytes())?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl ImageWriter for HdrWriter {
+    fn is_hdr(&self) -> bool {
+        return true;
+    }
+
+    fn write(&self, out: &mut dyn Write, width: u64, height: u64, pixels: &[Vec3], space: WorkingSpace) -> io::Result<()> {
+        let primaries = space.chromaticities().map(|xy| xy.to_string()).join(" ");
+        write!(out, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\nPRIMARIES={}\n\n-Y {} +X {}\n", primaries, height, width)?;
+
+        let mut scanline: Vec<[u8; 4]> = Vec::with_capacity(width as usize);
+        for row in pixels.chunks(width as usize) {
+            scanline.clear();
+            scanline.extend(row.iter().map(to_rgbe));
+
+            // Run-length encoding is only defined for widths in [8, 32767].
+            if !(8..0x8000).contains(&width) {
+                for rgbe in scanline.iter() {
+                    out.write_all(rgbe)?;
+                }
+                continue;
+            }
+
+            out.write_all(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8])?;
+            for channel in 0..4 {
+                let values: Vec<u8> = scanline.iter().map(|rgbe| rgbe[channel]).collect();
+                write_rle_channel(out, &values)?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl ImageWriter for ExrWriter {
+    fn is_hdr(&self) -> bool {
+        return true;
+    }
+
+    fn write(&self, out: &mut dyn Write, width: u64, height: u64, pixels: &[Vec3], space: WorkingSpace) -> io::Result<()> {
+        const PIXEL_TYPE_FLOAT: i32 = 2;
+
+        let mut header: Vec<u8> = Vec::new();
+        header.extend_from_slice(&20000630_i32.to_le_bytes()); // magic number
+        header.extend_from_slice(&2_i32.to_le_bytes());        // version 2, single-part scanline
+
+        // Channels are stored in alphabetical order.
+        let mut channels: Vec<u8> = Vec::new();
+        for name in ["B", "G", "R"] {
+            channels.extend_from_slice(name.as_bytes());
+            channels.push(0);
+            channels.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+            channels.extend_from_slice(&[0, 0, 0, 0]); // pLinear and reserved
+            channels.extend_from_slice(&1_i32.to_le_bytes()); // x sampling
+            channels.extend_from_slice(&1_i32.to_le_bytes()); // y sampling
+        }
+        channels.push(0);
+        write_exr_attribute(&mut header, "channels", "chlist", &channels);
+
+        let mut chromaticities: Vec<u8> = Vec::new();
+        for xy in space.chromaticities() {
+            chromaticities.extend_from_slice(&(xy as f32).to_le_bytes());
+        }
+        write_exr_attribute(&mut header, "chromaticities", "chromaticities", &chromaticities);
+
+        write_exr_attribute(&mut header, "compression", "compression", &[0]); // none
+
+        let mut window: Vec<u8> = Vec::new();
+        for value in [0, 0, width as i32 - 1, height as i32 - 1] {
+            window.extend_from_slice(&value.to_le_bytes());
+        }
+        write_exr_attribute(&mut header, "dataWindow", "box2i", &window);
+        write_exr_attribute(&mut header, "displayWindow", "box2i", &window);
+        write_exr_attribute(&mut header, "lineOrder", "lineOrder", &[0]); // increasing y
+        write_exr_attribute(&mut header, "pixelAspectRatio", "float", &1.0_f32.to_le_bytes());
+        let mut center: Vec<u8> = Vec::new();
+        center.extend_from_slice(&0.0_f32.to_le_bytes());
+        center.extend_from_slice(&0.0_f32.to_le_bytes());
+        write_exr_attribute(&mut header, "screenWindowCenter", "v2f", &center);
+        write_exr_attribute(&mut header, "screenWindowWidth", "float", &1.0_f32.to_le_bytes());
+        header.push(0);
+
+        // Without compression each chunk holds a single scanline: its y, its byte count, then the
+        // scanline of each channel in turn.
+        let line_size = width * 3 * 4;
+        let chunk_size = 4 + 4 + line_size;
+        let first_chunk = header.len() as u64 + height * 8;
+
+        out.write_all(&header)?;
+        for y in 0..height {
+            out.write_all(&(first_chunk + y * chunk_size).to_le_bytes())?;
+        }
+
+        for (y, row) in pixels.chunks(width as usize).enumerate() {
+            out.write_all(&(y as i32).to_le_bytes())?;
+            out.write_all(&(line_size as i32).to_le_bytes())?;
+            for channel in [2, 1, 0] {
+                for pixel in row.iter() {
+                    out.write_all(&(pixel.component(channel) as f32).to_le_bytes())?;
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+pub fn writer_for_path(path: &str) -> Result<Box<dyn ImageWriter>, String> {
+    // Picks the image format from the file extension.
+    let extension = Path::new(path).extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "ppm" => Ok(Box::new(PpmWriter)),
+        "png" => Ok(Box::new(PngWriter)),
+        "pfm" => Ok(Box::new(PfmWriter)),
+        "hdr" => Ok(Box::new(HdrWriter)),
+        "exr" => Ok(Box::new(ExrWriter)),
+        _ => Err(format!("unsupported image format for '{}'; use .ppm, .png, .pfm, .hdr or .exr", path))
+    }
+}
+
+pub fn write_film(path: &str, writer: &dyn ImageWriter, film: &Film, tone_mapper: Option<&ToneMapper>, aovs: &[Aov], space: WorkingSpace) -> io::Result<()> {
+    // Writes the film's image, rendered in the given working space, to path, and each of the
+    // requested output variables beside it. An image without a tone mapper holds data rather than
+    // light and is written as it is.
+    match tone_mapper {
+        Some(tone_mapper) => write_image(path, writer, film.width(), film.height(), &film.pixels(), tone_mapper, space)?,
+        None => write_data(path, writer, film.width(), film.height(), &film.pixels(), space)?
+    }
+
+    let aov_samples = film.aov_samples();
+    for aov in aovs.iter() {
+        let aov_pixels = aov.image(&aov_samples, writer.is_hdr(), space);
+        write_data(&aov.path(path), writer, film.width(), film.height(), &aov_pixels, space)?;
+    }
+    return Ok(());
+}
+
+pub fn write_sample_map(path: &str, writer: &dyn ImageWriter, film: &Film) -> io::Result<()> {
+    // Writes the number of samples taken in each pixel: as they are for high dynamic range
+    // formats, and otherwise scaled so the most sampled pixels are white. The counts aren't colors,
+    // so they're tagged with plain sRGB primaries.
+    let sample_counts = film.sample_counts();
+    let max_count = sample_counts.iter().copied().max().unwrap_or(0).max(1);
+    let scale = if writer.is_hdr() { 1.0 } else { 1.0 / max_count as f64 };
+    let pixels: Vec<Vec3> = sample_counts.iter().map(|count| {
+        let value = scale * *count as f64;
+        Vec3::new(value, value, value)
+    }).collect();
+    return write_data(path, writer, film.width(), film.height(), &pixels, WorkingSpace::Rec709);
+}
+
+pub fn write_image(path: &str, writer: &dyn ImageWriter, width: u64, height: u64, pixels: &[Vec3], tone_mapper: &ToneMapper, space: WorkingSpace) -> io::Result<()> {
+    // Writes the linear radiance pixels. High dynamic range formats get them untouched, for tone
+    // mapping downstream; for the others they're converted to sRGB primaries, tone mapped and
+    // encoded with the sRGB transfer function.
+    if writer.is_hdr() {
+        return write_data(path, writer, width, height, pixels, space);
+    }
+    let encoded: Vec<Vec3> = pixels.iter().map(|pixel| color::encode_srgb(tone_mapper.map(space.working_to_rec709(*pixel)))).collect();
+    return write_data(path, writer, width, height, &encoded, space);
+}
+
+pub fn write_data(path: &str, writer: &dyn ImageWriter, width: u64, height: u64, pixels: &[Vec3], space: WorkingSpace) -> io::Result<()> {
+    // Writes the pixels as they are, without tone mapping or encoding.
+    let mut out = BufWriter::new(File::create(path)?);
+    writer.write(&mut out, width, height, pixels, space)?;
+    return out.flush();
+}
+
+fn to_rgb_bytes(pixels: &[Vec3]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(pixels.len() * 3);
+    for pixel in pixels.iter() {
+        bytes.push(to_byte(pixel.x));
+        bytes.push(to_byte(pixel.y));
+        bytes.push(to_byte(pixel.z));
+    }
+    return bytes;
+}
+
+fn to_byte(component: f64) -> u8 {
+    // Translate the [0, 1] component value to byte range [0, 255]
+    let intensity = Interval::new(0.0, 0.999);
+    return (256.0 * intensity.clamp(component)) as u8;
+}
+
+fn to_rgbe(pixel: &Vec3) -> [u8; 4] {
+    // Shared exponent encoding: the mantissas are scaled so that the brightest component is in
+    // [128, 256). Non-finite components are written as 0, and components beyond the largest
+    // exponent saturate at the largest value RGBE holds.
+    let component = |value: f64| if value.is_finite() { value.max(0.0) } else { 0.0 };
+    let r = component(pixel.x);
+    let g = component(pixel.y);
+    let b = component(pixel.z);
+    let brightest = r.max(g).max(b);
+    if brightest < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    let exponent = (brightest.log2().floor() as i32 + 1).min(127);
+    let scale = 256.0 / 2.0_f64.powi(exponent);
+    let mantissa = |component: f64| (component * scale).min(255.0) as u8;
+    return [mantissa(r), mantissa(g), mantissa(b), (exponent + 128) as u8];
+}
+
+fn write_rle_channel(out: &mut dyn Write, values: &[u8]) -> io::Result<()> {
+    // Runs are written as (128 + length, value), and everything else as literal spans of
+    // (length, values...), each at most 127 bytes long.
+    const MIN_RUN: usize = 4;
+
+    let mut current = 0;
+    while current < values.len() {
+        // Find the start of the next run long enough to be worth encoding.
+        let mut run_start = current;
+        let mut run_length = 0;
+        while run_start < values.len() {
+            run_length = 1;
+            while run_start + run_length < values.len()
+                && run_length < 127
+                && values[run_start + run_length] == values[run_start] {
+                run_length += 1;
+            }
+            if run_length >= MIN_RUN {
+                break;
+            }
+            run_start += run_length;
+        }
+
+        while current < run_start {
+            let literal_length = (run_start - current).min(127);
+            out.write_all(&[literal_length as u8])?;
+            out.write_all(&values[current..current + literal_length])?;
+            current += literal_length;
+        }
+
+        if run_length >= MIN_RUN && run_start < values.len() {
+            out.write_all(&[128 + run_length as u8, values[run_start]])?;
+            current = run_start + run_length;
+        }
+    }
+    return Ok(());
+}
+
+fn write_exr_attribute(header: &mut Vec<u8>, name: &str, type_name: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(type_name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(writer: &dyn ImageWriter, width: u64, height: u64, pixels: &[Vec3]) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        writer.write(&mut out, width, height, pixels, WorkingSpace::Rec709).unwrap();
+        return out;
+    }
+
+    fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+        return f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    }
+
+    fn gray(value: f64) -> Vec3 {
+        return Vec3::new(value, value, value);
+    }
+
+    #[test]
+    fn ppm_header_and_bytes() {
+        let out = encode(&PpmWriter, 2, 1, &[Vec3::new(0.0, 0.5, 1.0), Vec3::new(0.25, 0.0, 0.0)]);
+        assert_eq!(out, b"P6\n2 1\n255\n\x00\x80\xff\x40\x00\x00");
+    }
+
+    #[test]
+    fn pfm_rows_are_bottom_up() {
+        let pixels = [Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0), Vec3::new(7.0, 8.0, 9.0), Vec3::new(10.0, 11.0, 12.0)];
+        let out = encode(&PfmWriter, 2, 2, &pixels);
+
+        let header = b"PF\n2 2\n-1.0\n";
+        assert_eq!(&out[..header.len()], header);
+        assert_eq!(out.len(), header.len() + 4 * 3 * 4);
+        let values: Vec<f32> = (0..12).map(|index| f32_at(&out, header.len() + 4 * index)).collect();
+        assert_eq!(values, [7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn rgbe_shares_the_brightest_exponent() {
+        assert_eq!(to_rgbe(&Vec3::new(1.0, 0.5, 0.0)), [128, 64, 0, 129]);
+        assert_eq!(to_rgbe(&Vec3::new(0.0, 0.0, 0.0)), [0, 0, 0, 0]);
+        assert_eq!(to_rgbe(&Vec3::new(-1.0, f64::NAN, 0.0)), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rgbe_saturates_huge_values_and_drops_non_finite_ones() {
+        assert_eq!(to_rgbe(&Vec3::new(f64::MAX, 1e300, 0.5)), [255, 255, 0, 255]);
+        assert_eq!(to_rgbe(&Vec3::new(2.0_f64.powi(126), 1.0, 0.0)), [128, 0, 0, 255]);
+        assert_eq!(to_rgbe(&Vec3::new(f64::INFINITY, 1.0, f64::NAN)), [0, 128, 0, 129]);
+        assert_eq!(to_rgbe(&Vec3::new(f64::NEG_INFINITY, f64::INFINITY, 0.0)), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn hdr_header_and_flat_narrow_scanlines() {
+        // Scanlines narrower than 8 pixels can't be run-length encoded, so are stored flat.
+        let out = encode(&HdrWriter, 2, 1, &[gray(1.0), gray(0.5)]);
+
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\nPRIMARIES=0.64 0.33 0.3 0.6 0.15 0.06 0.3127 0.329\n\n-Y 1 +X 2\n";
+        assert_eq!(&out[..header.len()], header);
+        assert_eq!(&out[header.len()..], &[128, 128, 128, 129, 128, 128, 128, 128]);
+    }
+
+    #[test]
+    fn hdr_scanlines_are_run_length_encoded_per_channel() {
+        let mut pixels = vec![gray(1.0); 6];
+        pixels.extend([Vec3::new(0.5, 0.5, 0.25), Vec3::new(0.25, 0.5, 0.5)]);
+        let out = encode(&HdrWriter, 8, 1, &pixels);
+
+        let header_length = out.iter().position(|&byte| byte == b'X').unwrap() + 4;
+        assert_eq!(&out[header_length - 10..header_length], b"-Y 1 +X 8\n");
+        // The scanline's marker and width, then each channel in turn: the six gray pixels and
+        // any equal values after them as a run, and what's left as a literal span.
+        let mut expected = vec![2, 2, 0, 8];
+        expected.extend([128 + 7, 128, 1, 64]);
+        expected.extend([128 + 8, 128]);
+        expected.extend([128 + 6, 128, 2, 64, 128]);
+        expected.extend([128 + 6, 129, 2, 128, 128]);
+        assert_eq!(&out[header_length..], &expected[..]);
+    }
+
+    fn rle(values: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        write_rle_channel(&mut out, values).unwrap();
+        return out;
+    }
+
+    #[test]
+    fn rle_run_boundaries() {
+        // Runs shorter than four stay in the literal spans around them.
+        assert_eq!(rle(&[1, 1, 1, 2]), [4, 1, 1, 1, 2]);
+        assert_eq!(rle(&[1, 1, 1, 1]), [128 + 4, 1]);
+        assert_eq!(rle(&[3, 4, 5, 5, 5, 5, 5, 6]), [2, 3, 4, 128 + 5, 5, 1, 6]);
+
+        // Runs and literal spans are at most 127 long.
+        assert_eq!(rle(&[9; 130]), [128 + 127, 9, 3, 9, 9, 9]);
+        assert_eq!(rle(&[9; 131]), [128 + 127, 9, 128 + 4, 9]);
+
+        let literals: Vec<u8> = (0..200).map(|value| (value % 2) as u8).collect();
+        let out = rle(&literals);
+        assert_eq!(out.len(), 1 + 127 + 1 + 73);
+        assert_eq!(out[0], 127);
+        assert_eq!(&out[1..128], &literals[..127]);
+        assert_eq!(out[128], 73);
+        assert_eq!(&out[129..], &literals[127..]);
+    }
+
+    #[test]
+    fn exr_header_offsets_and_scanlines() {
+        let pixels = [Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0), Vec3::new(7.0, 8.0, 9.0), Vec3::new(10.0, 11.0, 12.0)];
+        let out = encode(&ExrWriter, 2, 2, &pixels);
+
+        assert_eq!(&out[..8], &[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+        let channels = b"channels\0chlist\0";
+        assert_eq!(&out[8..8 + channels.len()], channels);
+
+        // The header ends after the last attribute's value and a zero byte, followed by the
+        // offset of each scanline's chunk.
+        let last_attribute = b"screenWindowWidth\0float\0\x04\0\0\0";
+        let last = out.windows(last_attribute.len()).position(|window| window == last_attribute).unwrap();
+        let header_length = last + last_attribute.len() + 4 + 1;
+        assert_eq!(out[header_length - 1], 0);
+        let offset = |y: usize| u64::from_le_bytes(out[header_length + 8 * y..header_length + 8 * y + 8].try_into().unwrap()) as usize;
+        let chunk_length = 4 + 4 + 2 * 3 * 4;
+        assert_eq!(offset(0), header_length + 2 * 8);
+        assert_eq!(offset(1), offset(0) + chunk_length);
+        assert_eq!(out.len(), offset(1) + chunk_length);
+
+        // Chunks go from the top row down, each holding its y, its size, then the B, G and R
+        // channels in turn.
+        for y in 0..2 {
+            let chunk = offset(y);
+            assert_eq!(i32::from_le_bytes(out[chunk..chunk + 4].try_into().unwrap()), y as i32);
+            assert_eq!(i32::from_le_bytes(out[chunk + 4..chunk + 8].try_into().unwrap()), 24);
+            let values: Vec<f32> = (0..6).map(|index| f32_at(&out, chunk + 8 + 4 * index)).collect();
+            let row = &pixels[2 * y..2 * y + 2];
+            let expected: Vec<f32> = [2, 1, 0].iter()
+                .flat_map(|&channel| row.iter().map(move |pixel| pixel.component(channel) as f32))
+                .collect();
+            assert_eq!(values, expected);
+        }
+    }
+}
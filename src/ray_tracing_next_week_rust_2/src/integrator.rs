@@ -0,0 +1,29 @@
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+use crate::material::Material;
+use std::sync::Arc;
+
+// Light carried to the camera by a path from the lights, to be added to the pixels around the
+// image position it reaches, with pixel centers at whole numbers.
+pub struct Splat {
+    pub x: f64,
+    pub y: f64,
+    pub radiance: Vec3
+}
+
+// How the value of a camera ray is found: the light transport of the render, or a debug view of
+// the scene.
+pub trait Integrator : Send + Sync {
+    // The light arriving at the camera back along the ray, in the working space; for debug views,
+    // the value to show. Light found reaching the camera through other pixels, by integrators
+    // that also trace paths from the lights, is added to splats.
+    fn radiance(&self, ray: Ray, scene: &Scene, sampler: &mut dyn Sampler, splats: &mut Vec<Splat>, mats: &[Arc<dyn Material>]) -> Vec3;
+
+    // Whether the values are data, written as they are rather than converted and tone mapped
+    // like light.
+    fn is_data(&self) -> bool {
+        return false;
+    }
+}
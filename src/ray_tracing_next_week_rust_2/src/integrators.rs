@@ -0,0 +1,229 @@
+use std::sync::Arc;
+
+use clap::ValueEnum;
+
+use crate::integrator::{Integrator, Splat};
+use crate::camera::Camera;
+use crate::scene::Scene;
+use crate::hittable::Hittable;
+use crate::material::Material;
+use crate::hitrecord::HitRecord;
+use crate::ray::Ray;
+use crate::interval::Interval;
+use crate::sampler::Sampler;
+use crate::vec3::Vec3;
+use crate::mis::MisHeuristic;
+use crate::onb::Onb;
+use crate::rtweekend::sample_cosine_direction;
+use crate::aov;
+use crate::bdpt::BdptIntegrator;
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum IntegratorKind {
+    Path,     // Global illumination, by tracing paths through the scene
+    Bdpt,     // Global illumination, by joining paths traced from the camera and from the lights
+    Direct,   // Only light reaching the first hit straight from the lights and background
+    Ao,       // Ambient occlusion: how open the hemisphere above the first hit is
+    Normals,  // Shading normals, mapped from [-1, 1] to [0, 1]
+    Uvs,      // Texture coordinates, in the red and green channels
+    Depth,    // Distance along the view direction: white at the camera, half gray at the focus distance
+    Material  // A distinct color for each material
+}
+
+impl IntegratorKind {
+    pub fn create(&self, camera: &Camera, max_depth: u16, russian_roulette_depth: u16, mis_heuristic: MisHeuristic, ao_distance: f64) -> Box<dyn Integrator> {
+        return match self {
+            IntegratorKind::Path => Box::new(PathIntegrator::new(max_depth, russian_roulette_depth, mis_heuristic)),
+            IntegratorKind::Bdpt => Box::new(BdptIntegrator::new(max_depth, russian_roulette_depth, mis_heuristic, *camera)),
+            // Paths that end after their first scatter only find the light reaching the first hit
+            // directly, by sampling the lights or by scattering into them.
+            IntegratorKind::Direct => Box::new(PathIntegrator::new(max_depth.min(2), russian_roulette_depth, mis_heuristic)),
+            IntegratorKind::Ao => Box::new(AmbientOcclusionIntegrator::new(ao_distance)),
+            IntegratorKind::Normals => Box::new(DebugIntegrator::new(DebugView::Normals, *camera)),
+            IntegratorKind::Uvs => Box::new(DebugIntegrator::new(DebugView::Uvs, *camera)),
+            IntegratorKind::Depth => Box::new(DebugIntegrator::new(DebugView::Depth, *camera)),
+            IntegratorKind::Material => Box::new(DebugIntegrator::new(DebugView::Material, *camera))
+        };
+    }
+}
+
+pub struct PathIntegrator {
+    max_depth: u16,               // Maximum number of ray bounces into the scene
+    russian_roulette_depth: u16,  // Bounces after which paths may be ended at random
+    mis_heuristic: MisHeuristic   // Weighting of light found by both scattering and light sampling
+}
+
+pub struct AmbientOcclusionIntegrator {
+    distance: f64 // How far away geometry still occludes
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DebugView {
+    Normals,
+    Uvs,
+    Depth,
+    Material
+}
+
+pub struct DebugIntegrator {
+    view: DebugView,
+    camera: Camera // For the depth of hits
+}
+
+impl PathIntegrator {
+    pub fn new(max_depth: u16, russian_roulette_depth: u16, mis_heuristic: MisHeuristic) -> Self {
+        Self {
+            max_depth, russian_roulette_depth, mis_heuristic
+        }
+    }
+
+    fn direct_light(&self, ray: &Ray, hit: &HitRecord, scene: &Scene, sampler: &mut dyn Sampler, mats: &[Arc<dyn Material>]) -> Vec3 {
+        // Light reaching a non-specular hit straight from a point picked on the lights, and from a
+        // direction picked from the background, through shadow rays.
+        let mut radiance = Vec3::new(0.0, 0.0, 0.0);
+
+        if !scene.lights.is_empty() {
+            let direction = scene.lights.random(hit.p, ray.tm, sampler).normalized();
+            let light_pdf = scene.lights.pdf_value(hit.p, direction, ray.tm);
+            let scattering_pdf = mats[hit.material as usize].scattering_pdf(ray, hit, direction);
+            let shadow_ray = Ray::new2(hit.p, direction, ray.tm);
+            // The nearest light in the direction is the one lighting the hit, if nothing is in
+            // the way.
+            if light_pdf > 0.0 && scattering_pdf > 0.0
+                && let Some(light_hit) = scene.lights.hit(&shadow_ray, &Interval::new(0.0001, f64::INFINITY), sampler)
+                && scene.world.hit(&shadow_ray, &Interval::new(0.0001, light_hit.t - 0.0001), sampler).is_none() {
+                let emitted = mats[light_hit.material as usize].emitted(light_hit.u, light_hit.v, light_hit.p);
+                let weight = self.mis_heuristic.weight(light_pdf, scattering_pdf);
+                let bsdf = mats[hit.material as usize].eval(ray, hit, direction);
+                radiance = radiance + (weight / light_pdf) * bsdf * emitted;
+            }
+        }
+
+        if let Some(light) = scene.background.sample(sampler) {
+            let scattering_pdf = mats[hit.material as usize].scattering_pdf(ray, hit, light.direction);
+            let shadow_ray = Ray::new2(hit.p, light.direction, ray.tm);
+            if scattering_pdf > 0.0 && scene.world.hit(&shadow_ray, &Interval::new(0.0001, f64::INFINITY), sampler).is_none() {
+                let weight = self.mis_heuristic.weight(light.pdf, scattering_pdf);
+                let bsdf = mats[hit.material as usize].eval(ray, hit, light.direction);
+                radiance = radiance + (weight / light.pdf) * bsdf * light.color;
+            }
+        }
+        return radiance;
+    }
+}
+
+impl AmbientOcclusionIntegrator {
+    pub fn new(distance: f64) -> Self {
+        Self {
+            distance
+        }
+    }
+}
+
+impl DebugIntegrator {
+    pub fn new(view: DebugView, camera: Camera) -> Self {
+        Self {
+            view, camera
+        }
+    }
+}
+
+impl Integrator for PathIntegrator {
+    fn radiance(&self, mut ray: Ray, scene: &Scene, sampler: &mut dyn Sampler, _splats: &mut Vec<Splat>, mats: &[Arc<dyn Material>]) -> Vec3 {
+        // Follows the path bounce by bounce, carrying the product of the attenuations so far.
+        let mut radiance = Vec3::new(0.0, 0.0, 0.0);
+        let mut throughput = Vec3::new(1.0, 1.0, 1.0);
+        // The density of the last scatter, if the lights and background were also sampled from
+        // there.
+        let mut scattering_pdf: Option<f64> = None;
+        for depth in 0..self.max_depth {
+            if depth >= self.russian_roulette_depth {
+                // Ends the path with a probability that grows as its throughput falls, and scales
+                // up the survivors so the estimate stays unbiased. Taken even when the path
+                // survives for certain, so the following dimensions stay in step.
+                let survival = throughput.max_component().min(1.0);
+                if sampler.get_1d() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+
+            // Light that sampling the lights or the background could also have found is shared
+            // between the two by multiple importance sampling.
+            // NOTE: start interval at 0.0001 to avoid shadow acne from intersecting with same object
+            let Some(hit) = scene.world.hit(&ray, &Interval::new(0.0001, f64::INFINITY), sampler) else {
+                let direction = ray.direction.normalized();
+                let weight = scattering_pdf.map_or(1.0, |pdf| self.mis_heuristic.weight(pdf, scene.background.pdf(direction)));
+                radiance = radiance + weight * throughput * scene.background.color(direction);
+                break;
+            };
+            let mat = &mats[hit.material as usize];
+            let emitted = mat.emitted(hit.u, hit.v, hit.p);
+            let weight = match scattering_pdf {
+                Some(pdf) if !emitted.near_zero() => self.mis_heuristic.weight(pdf, scene.lights.pdf_value(ray.origin, ray.direction, ray.tm)),
+                _ => 1.0
+            };
+            radiance = radiance + weight * throughput * emitted;
+
+            let Some(scattered) = mat.scatter(&ray, &hit, sampler) else {
+                break;
+            };
+
+            // Surfaces that don't scatter specularly are also lit directly, if the path could still
+            // reach the light by scattering.
+            scattering_pdf = None;
+            if !scattered.specular && scattered.pdf > 0.0 && depth + 1 < self.max_depth {
+                radiance = radiance + throughput * self.direct_light(&ray, &hit, scene, sampler, mats);
+                scattering_pdf = Some(scattered.pdf);
+            }
+
+            throughput = throughput * scattered.attenuation;
+            ray = scattered.scattered;
+        }
+        return radiance;
+    }
+}
+
+impl Integrator for AmbientOcclusionIntegrator {
+    fn radiance(&self, ray: Ray, scene: &Scene, sampler: &mut dyn Sampler, _splats: &mut Vec<Splat>, _mats: &[Arc<dyn Material>]) -> Vec3 {
+        // Whether a cosine weighted direction above the first hit escapes, so that the mean over
+        // the pixel's samples is the cosine weighted fraction of the hemisphere left open.
+        let Some(hit) = scene.world.hit(&ray, &Interval::new(0.0001, f64::INFINITY), sampler) else {
+            return Vec3::new(0.0, 0.0, 0.0);
+        };
+        let direction = Onb::new(hit.normal).transform(sample_cosine_direction(sampler.get_2d()));
+        let occlusion_ray = Ray::new2(hit.p, direction, ray.tm);
+        if scene.world.hit(&occlusion_ray, &Interval::new(0.0001, self.distance), sampler).is_some() {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+        return Vec3::new(1.0, 1.0, 1.0);
+    }
+
+    fn is_data(&self) -> bool {
+        return true;
+    }
+}
+
+impl Integrator for DebugIntegrator {
+    fn radiance(&self, ray: Ray, scene: &Scene, sampler: &mut dyn Sampler, _splats: &mut Vec<Splat>, _mats: &[Arc<dyn Material>]) -> Vec3 {
+        // The value of the view at the first hit, already in [0, 1] for viewing; black where
+        // nothing is hit.
+        let Some(hit) = scene.world.hit(&ray, &Interval::new(0.0001, f64::INFINITY), sampler) else {
+            return Vec3::new(0.0, 0.0, 0.0);
+        };
+        return match self.view {
+            DebugView::Normals => 0.5 * hit.normal + Vec3::new(0.5, 0.5, 0.5),
+            DebugView::Uvs => Vec3::new(hit.u, hit.v, 0.0),
+            DebugView::Depth => {
+                let value = 1.0 / (1.0 + self.camera.depth(hit.p).max(0.0) / self.camera.focus_distance());
+                Vec3::new(value, value, value)
+            }
+            // Numbered from 1 like the material id output variable, leaving 0 for misses.
+            DebugView::Material => aov::id_color(hit.material as usize + 1)
+        };
+    }
+
+    fn is_data(&self) -> bool {
+        return true;
+    }
+}
@@ -1,10 +1,22 @@
-use std::time::Instant;
-use std::env;
-use std::sync::Arc;
-
-use camera::Camera;
-use crate::hittable::Hittable;
-use crate::material::Material;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use clap::Parser;
+use crate::scenes::SceneParts;
+use crate::tonemap::{ToneMapOperator, ToneMapper};
+use crate::color::WorkingSpace;
+use crate::aov::Aov;
+use crate::film::Film;
+use crate::tiles::TileOrder;
+use crate::crop::{CropOutput, CropWindow};
+use crate::progress::{RenderCallback, StderrProgress};
+use crate::rtweekend::Rng;
+use crate::sampler::SamplerKind;
+use crate::filter::{Filter, FilterKind};
+use crate::backgrounds::BackgroundKind;
+use crate::environmentmap::EnvironmentMap;
+use crate::physicalsky::PhysicalSky;
+use crate::mis::MisHeuristic;
+use crate::integrators::IntegratorKind;
 
 mod vec3;
 mod ray;
@@ -28,34 +40,402 @@ mod quad;
 mod translate;
 mod rotatey;
 mod constantmedium;
+mod imagewriter;
+mod tonemap;
+mod color;
+mod aov;
+mod film;
+mod tiles;
+mod progress;
+mod crop;
+mod sampler;
+mod filter;
+mod background;
+mod backgrounds;
+mod imagereader;
+mod distribution;
+mod environmentmap;
+mod physicalsky;
+mod scene;
+mod mis;
+mod onb;
+mod integrator;
+mod integrators;
+mod bdpt;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Ray Tracing the Next Week renderer")]
+struct Args {
+    /// Scene to render, by name or number (see --list-scenes)
+    #[arg(value_name = "SCENE", conflicts_with = "scene")]
+    scene_positional: Option<String>,
+
+    /// Scene to render, by name or number (see --list-scenes)
+    #[arg(short, long)]
+    scene: Option<String>,
+
+    /// Output image file; the format (.ppm, .png, .pfm, .hdr or .exr) is chosen by its extension
+    #[arg(short, long, default_value = "render.ppm")]
+    output: String,
+
+    /// Directory containing texture images such as earthmap.jpg
+    #[arg(short, long, default_value = ".")]
+    textures: String,
+
+    /// Image width in pixels, overriding the scene's width
+    #[arg(short, long, value_parser = clap::value_parser!(u64).range(1..))]
+    width: Option<u64>,
+
+    /// Samples per pixel, overriding the camera default
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    spp: Option<u64>,
+
+    /// How the light of each camera ray is found: path tracing, or a quicker partial or debug view
+    #[arg(long, value_enum, default_value_t = IntegratorKind::Path)]
+    integrator: IntegratorKind,
+
+    /// Maximum number of ray bounces
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u16).range(1..))]
+    max_depth: u16,
+
+    /// Bounces every path gets before Russian roulette may end it
+    #[arg(long, value_name = "DEPTH", default_value_t = 3)]
+    rr_depth: u16,
+
+    /// How light that both scattering and sampling the lights could have found is weighted
+    /// between them
+    #[arg(long, value_enum, default_value_t = MisHeuristic::Power)]
+    mis_heuristic: MisHeuristic,
+
+    /// Distance within which geometry occludes, for --integrator ao (defaults to any distance)
+    #[arg(long, value_name = "DISTANCE")]
+    ao_distance: Option<f64>,
+
+    /// Vertical field of view in degrees, overriding the scene's
+    #[arg(long, value_name = "DEGREES")]
+    vfov: Option<f64>,
+
+    /// Lens aperture as the variation angle of the rays through each pixel in degrees, overriding
+    /// the scene's; 0 puts everything in focus
+    #[arg(long, value_name = "DEGREES")]
+    defocus_angle: Option<f64>,
+
+    /// Distance from the camera to the plane in focus, overriding the scene's
+    #[arg(long, value_name = "DISTANCE", conflicts_with = "auto_focus")]
+    focus_distance: Option<f64>,
+
+    /// Focus on the point the camera looks at
+    #[arg(long)]
+    auto_focus: bool,
+
+    /// Light from beyond the scene, overriding the scene's background
+    #[arg(long, value_enum, conflicts_with = "environment")]
+    background: Option<BackgroundKind>,
+
+    /// Elevation of the sun above the horizon for a physical sky, from --background physical-sky
+    /// or the scene, in degrees (defaults to 45, or the scene's own)
+    #[arg(long, value_name = "DEGREES")]
+    sun_elevation: Option<f64>,
+
+    /// Direction of the sun for a physical sky, in degrees around the vertical axis from -z
+    /// towards +x (defaults to 0, or the scene's own)
+    #[arg(long, value_name = "DEGREES", allow_negative_numbers = true)]
+    sun_azimuth: Option<f64>,
+
+    /// Haziness of the air for a physical sky, from 2 (very clear) to 10 (hazy) (defaults to 3,
+    /// or the scene's own)
+    #[arg(long)]
+    turbidity: Option<f64>,
+
+    /// Equirectangular environment map (.hdr, .pfm, .png or .jpg) lighting the scene in place of
+    /// its background; the top of the image is straight up
+    #[arg(long, value_name = "FILE")]
+    environment: Option<String>,
+
+    /// Rotation of the environment map about the vertical axis, in degrees
+    #[arg(long, value_name = "DEGREES", default_value_t = 0.0, allow_negative_numbers = true)]
+    environment_rotation: f64,
+
+    /// Scale applied to the environment map's radiance
+    #[arg(long, default_value_t = 1.0)]
+    environment_intensity: f64,
+
+    /// Tone mapping operator applied before writing .ppm and .png images
+    #[arg(long, value_enum, default_value_t = ToneMapOperator::Clamp)]
+    tonemap: ToneMapOperator,
+
+    /// Exposure adjustment in stops (EV), applied before tone mapping
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    exposure: f64,
+
+    /// Scene value mapped to white by the reinhard-extended and hable operators
+    #[arg(long)]
+    white_point: Option<f64>,
+
+    /// Linear color space used for rendering and for .pfm, .hdr and .exr output
+    #[arg(long, value_enum, default_value_t = WorkingSpace::Rec709)]
+    working_space: WorkingSpace,
+
+    /// First-hit passes to write alongside the image, each to <output>.<pass>.<extension>
+    #[arg(long, value_enum, value_delimiter = ',')]
+    aov: Vec<Aov>,
+
+    /// Width and height in pixels of the tiles the image is rendered in
+    #[arg(long, default_value_t = 32, value_parser = clap::value_parser!(u64).range(1..))]
+    tile_size: u64,
+
+    /// Order in which the tiles are rendered
+    #[arg(long, value_enum, default_value_t = TileOrder::Hilbert)]
+    tile_order: TileOrder,
+
+    /// Only render the pixels from X0,Y0 up to X1,Y1
+    #[arg(long, value_name = "X0,Y0,X1,Y1", value_parser = crop::parse_pixels, conflicts_with = "crop_normalized")]
+    crop: Option<CropWindow>,
+
+    /// Only render the window from X0,Y0 up to X1,Y1, given as fractions of the image size
+    #[arg(long, value_name = "X0,Y0,X1,Y1", value_parser = crop::parse_normalized)]
+    crop_normalized: Option<CropWindow>,
+
+    /// Whether a crop window is written on its own or in place in the full frame (resume a full
+    /// render's checkpoint to composite over it)
+    #[arg(long, value_enum, default_value_t = CropOutput::Cropped)]
+    crop_output: CropOutput,
+
+    /// Samples per pixel in each progressive pass (defaults to all of them in one pass)
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pass_spp: Option<u64>,
+
+    /// Save the raw accumulation buffer to this file between passes, to resume the render later
+    #[arg(long, value_name = "FILE")]
+    checkpoint: Option<String>,
+
+    /// Minimum number of seconds between checkpoints; the final pass is always saved
+    #[arg(long, value_name = "SECONDS", default_value_t = 60)]
+    checkpoint_interval: u64,
+
+    /// Continue the render saved in this checkpoint, adding passes until --spp samples per pixel
+    /// are reached; the checkpoint is updated unless --checkpoint names another file
+    #[arg(long, value_name = "FILE")]
+    resume: Option<String>,
+
+    /// Seed for the random numbers used to build the scene and to render it; the same seed gives
+    /// the same image whatever the thread count
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Sampler generating the pixel, lens, time and scattering samples; the low-discrepancy ones
+    /// converge with fewer samples per pixel than random
+    #[arg(long, value_enum, default_value_t = SamplerKind::Sobol)]
+    sampler: SamplerKind,
+
+    /// Pixel reconstruction filter the samples are weighted with
+    #[arg(long, value_enum, default_value_t = FilterKind::Box)]
+    filter: FilterKind,
 
-fn main() {
-    let file_name = "e:\\temp\\render\\test.ppm";
-    
-    let args: Vec<String> = env::args().collect();
+    /// Radius of the reconstruction filter in pixels (defaults to 0.5 for box, 1 for tent, 1.5 for
+    /// gaussian and blackman-harris, 2 for mitchell and 3 for lanczos)
+    #[arg(long)]
+    filter_radius: Option<f64>,
 
-    let camera: Camera;
-    let world_hittable: Box<dyn Hittable>;
-    let mats: Vec<Arc<dyn Material>>;
+    /// Relative error of a pixel's mean below which it stops being sampled, turning on adaptive
+    /// sampling; pixels then get between --min-spp and --spp samples
+    #[arg(long, value_name = "ERROR")]
+    adaptive_threshold: Option<f64>,
 
-    let texture_path = "E:/temp";
+    /// Samples every pixel gets before adaptive sampling may stop it; raise it for scenes lit by
+    /// small lights, where dark pixels can go many samples without finding the light
+    #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u64).range(1..))]
+    min_spp: u64,
 
-    match args[1].as_str() {
-        "1" => (camera, world_hittable, mats) = scenes::bouncing_spheres(),
-        "2" => (camera, world_hittable, mats) = scenes::checkered_spheres(),
-        "3" => (camera, world_hittable, mats) = scenes::earth(texture_path),
-        "4" => (camera, world_hittable, mats) = scenes::perlin_spheres(),
-        "5" => (camera, world_hittable, mats) = scenes::quads(),
-        "6" => (camera, world_hittable, mats) = scenes::simple_light(),
-        "7" => (camera, world_hittable, mats) = scenes::cornell_box(),
-        "8" => (camera, world_hittable, mats) = scenes::cornell_smoke(),
-        "9" => (camera, world_hittable, mats) = scenes::final_scene(texture_path),
-        _ => panic!("unrecognised {}", args[1].as_str())
+    /// Write the number of samples taken in each pixel to this image, scaled so the most sampled
+    /// pixels are white unless it's .pfm, .hdr or .exr
+    #[arg(long, value_name = "FILE")]
+    sample_map: Option<String>,
+
+    /// Number of render threads (defaults to one per logical core)
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u64).range(1..))]
+    threads: Option<u64>,
+
+    /// Don't report progress on stderr
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// List the available scenes and exit
+    #[arg(long)]
+    list_scenes: bool
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    if args.list_scenes {
+        for (index, scene) in scenes::SCENES.iter().enumerate() {
+            println!("{:>2}  {:<18} {}", index + 1, scene.name, scene.description);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let scene_name = args.scene.as_ref().or(args.scene_positional.as_ref())
+        .ok_or("no scene given; pass --scene <NAME> (see --list-scenes)")?;
+    let scene = scenes::find_scene(scene_name)
+        .ok_or(format!("unrecognised scene '{}'; see --list-scenes", scene_name))?;
+
+    let writer = imagewriter::writer_for_path(&args.output)?;
+    let sample_map_writer = args.sample_map.as_ref().map(|path| imagewriter::writer_for_path(path)).transpose()?;
+    let white_point = args.white_point.unwrap_or(args.tonemap.default_white_point());
+    let tone_mapper = ToneMapper::new(args.tonemap, args.exposure, white_point);
+
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads as usize)
+            .build_global()
+            .map_err(|err| format!("unable to start {} render threads: {}", threads, err))?;
+    }
+
+    // Scene colors are converted into the working space as the scene is built.
+    let (mut builder, mut scene, mats): SceneParts = scenes::load_scene(scene, &args.textures, &mut Rng::new(args.seed), args.working_space)?;
+    if let Some(kind) = args.background {
+        scene.background = kind.create(scene.working_space);
+    }
+    if let Some(path) = &args.environment {
+        if args.environment_intensity < 0.0 || !args.environment_intensity.is_finite() {
+            return Err(format!("the environment intensity must be a non-negative scale, not {}", args.environment_intensity));
+        }
+        scene.background = Box::new(EnvironmentMap::new(path, args.environment_rotation, args.environment_intensity, scene.working_space)?);
+    }
+    // The sun options adjust whichever physical sky the scene ends up with, its own or the one
+    // from --background; the settings not given keep that sky's.
+    if args.sun_elevation.is_some() || args.sun_azimuth.is_some() || args.turbidity.is_some() {
+        let sky = scene.background.sky_settings().ok_or(
+            "--sun-elevation, --sun-azimuth and --turbidity only apply to a physical sky, from --background physical-sky or the scene")?;
+        let sun_elevation = args.sun_elevation.unwrap_or(sky.sun_elevation);
+        let turbidity = args.turbidity.unwrap_or(sky.turbidity);
+        if !(0.0..=90.0).contains(&sun_elevation) {
+            return Err(format!("the sun elevation must be between 0 and 90 degrees, not {}", sun_elevation));
+        }
+        if !(2.0..=10.0).contains(&turbidity) {
+            return Err(format!("the turbidity must be between 2 and 10, not {}", turbidity));
+        }
+        scene.background = Box::new(PhysicalSky::new(sun_elevation, args.sun_azimuth.unwrap_or(sky.sun_azimuth), turbidity, scene.working_space));
+    }
+
+    if let Some(width) = args.width {
+        builder = builder.image_width(width);
+    }
+    if let Some(spp) = args.spp {
+        builder = builder.samples_per_pixel(spp);
     }
+    if let Some(vfov) = args.vfov {
+        if vfov <= 0.0 || vfov >= 180.0 {
+            return Err(format!("the vertical field of view must be between 0 and 180 degrees, not {}", vfov));
+        }
+        builder = builder.vfov(vfov);
+    }
+    if let Some(defocus_angle) = args.defocus_angle {
+        if !(0.0..180.0).contains(&defocus_angle) {
+            return Err(format!("the defocus angle must be at least 0 and below 180 degrees, not {}", defocus_angle));
+        }
+        builder = builder.defocus_angle(defocus_angle);
+    }
+    if let Some(focus_distance) = args.focus_distance {
+        if focus_distance <= 0.0 || !focus_distance.is_finite() {
+            return Err(format!("the focus distance must be a positive distance, not {}", focus_distance));
+        }
+        builder = builder.focus_distance(focus_distance);
+    }
+    if args.auto_focus {
+        builder = builder.focus_on_look_at();
+    }
+    builder = builder.tile_size(args.tile_size).tile_order(args.tile_order).seed(args.seed).sampler(args.sampler);
+    let filter_radius = args.filter_radius.unwrap_or(args.filter.default_radius());
+    if filter_radius <= 0.0 || !filter_radius.is_finite() {
+        return Err(format!("the filter radius must be a positive number of pixels, not {}", filter_radius));
+    }
+    builder = builder.filter(Filter::new(args.filter, filter_radius));
+    if let Some(threshold) = args.adaptive_threshold {
+        if threshold <= 0.0 || !threshold.is_finite() {
+            return Err(format!("the adaptive threshold must be a positive relative error, not {}", threshold));
+        }
+        builder = builder.adaptive_sampling(threshold, args.min_spp);
+    }
+    let crop_window = args.crop.or(args.crop_normalized);
+    if let Some(crop_window) = crop_window {
+        builder = builder.crop_window(crop_window);
+    }
+    let camera = builder.build();
+    if let Some(CropWindow::Pixels([x0, y0, _, _])) = crop_window && (x0 >= camera.image_width || y0 >= camera.image_height()) {
+        return Err(format!("the crop window starts outside the {}x{} image", camera.image_width, camera.image_height()));
+    }
+    let region = camera.render_region();
+
+    let ao_distance = args.ao_distance.unwrap_or(f64::INFINITY);
+    if ao_distance <= 0.0 || ao_distance.is_nan() {
+        return Err(format!("the occlusion distance must be a positive distance, not {}", ao_distance));
+    }
+    let integrator = args.integrator.create(&camera, args.max_depth, args.rr_depth, args.mis_heuristic, ao_distance);
 
     let now = Instant::now();
-    camera.render(&world_hittable, file_name, &mats);
+    let mut film = match &args.resume {
+        Some(path) => {
+            let film = Film::load(path, camera.image_width, camera.image_height())
+                .map_err(|err| format!("unable to load checkpoint '{}': {}", path, err))?;
+            if !args.aov.is_empty() && !film.has_aovs() {
+                return Err(format!("checkpoint '{}' was saved without output variables, so --aov can't be used", path));
+            }
+            film
+        }
+        None => camera.new_film(!args.aov.is_empty())
+    };
+
+    let checkpoint = args.checkpoint.as_ref().or(args.resume.as_ref());
+    let checkpoint_interval = Duration::from_secs(args.checkpoint_interval);
+    let mut last_checkpoint = Instant::now();
+    // Adaptive sampling checks which pixels have converged between passes, by default after every
+    // --min-spp samples.
+    let default_pass_samples = if args.adaptive_threshold.is_some() { args.min_spp } else { camera.samples_per_pixel() };
+    let pass_samples = args.pass_spp.unwrap_or(default_pass_samples);
+
+    let remaining_samples = camera.samples_per_pixel().saturating_sub(film.min_sample_count(&region));
+    let progress = StderrProgress::new(camera.tile_count() * remaining_samples.div_ceil(pass_samples), region.pixel_count() * remaining_samples);
+    let callback: Option<&dyn RenderCallback> = if args.quiet { None } else { Some(&progress) };
+
+    while !camera.render_done(&film) {
+        camera.render(&scene, integrator.as_ref(), &mut film, pass_samples, callback, &mats);
+
+        let finished = camera.render_done(&film);
+        if let Some(path) = checkpoint && (finished || last_checkpoint.elapsed() >= checkpoint_interval) {
+            film.save(path).map_err(|err| format!("unable to save checkpoint '{}': {}", path, err))?;
+            last_checkpoint = Instant::now();
+        }
+    }
+
+    if !args.quiet {
+        progress.finish();
+    }
+
+    if region.pixel_count() < film.width() * film.height() && args.crop_output == CropOutput::Cropped {
+        film = film.cropped(&region);
+    }
+    // Debug views are written as they are, without tone mapping.
+    let tone_mapper = if integrator.is_data() { None } else { Some(&tone_mapper) };
+    imagewriter::write_film(&args.output, writer.as_ref(), &film, tone_mapper, &args.aov, scene.working_space)
+        .map_err(|err| format!("unable to write '{}': {}", args.output, err))?;
+    if let (Some(path), Some(sample_map_writer)) = (&args.sample_map, sample_map_writer) {
+        imagewriter::write_sample_map(path, sample_map_writer.as_ref(), &film)
+            .map_err(|err| format!("unable to write '{}': {}", path, err))?;
+    }
 
     let elapsed_time = now.elapsed();
     println!("done in {}ms", elapsed_time.as_millis());
+    return Ok(());
 }
@@ -1,24 +1,63 @@
 use crate::ray::Ray;
 use crate::hitrecord::HitRecord;
 use crate::vec3::Vec3;
+use crate::sampler::Sampler;
 
 pub struct ScatterResult {
     pub attenuation: Vec3,
-    pub scattered: Ray
+    pub scattered: Ray,
+    pub pdf: f64,       // Density over solid angle of picking the scattered direction
+    pub specular: bool  // Whether the direction was the only one possible, so no density applies
 }
 
 impl ScatterResult {
-    pub fn new(attenuation: Vec3, scattered: Ray) -> Self {
+    pub fn new(attenuation: Vec3, scattered: Ray, pdf: f64) -> Self {
         Self {
-            attenuation, scattered
+            attenuation, scattered, pdf, specular: false
         }
-    }    
+    }
+
+    pub fn specular(attenuation: Vec3, scattered: Ray) -> Self {
+        Self {
+            attenuation, scattered, pdf: 0.0, specular: true
+        }
+    }
 }
 
 pub trait Material : Send + Sync {
-    fn scatter(&self, _r_in: &Ray, _hit_record: &HitRecord) -> Option<ScatterResult>;
+    // Picks the direction light leaving along r_in arrives from, with an attenuation of eval
+    // over the pdf of picking it.
+    fn scatter(&self, _r_in: &Ray, _hit_record: &HitRecord, _sampler: &mut dyn Sampler) -> Option<ScatterResult>;
+
+    // The BSDF for light arriving from direction and leaving along r_in, times the cosine of
+    // direction to the normal; for media, the phase function. Materials that only scatter
+    // specularly can't be evaluated for a given direction and return 0.
+    fn eval(&self, _r_in: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> Vec3 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+
+    // Probability density over solid angle of scatter picking the direction. Materials that
+    // don't scatter specularly are also lit by sampling the lights; the others return 0.
+    fn scattering_pdf(&self, _r_in: &Ray, _hit_record: &HitRecord, _direction: Vec3) -> f64 {
+        return 0.0;
+    }
+
+    // Whether the material scatters light within a volume, like the phase function of a medium,
+    // so that its hits aren't on a surface and have no meaningful normal.
+    fn is_volume(&self) -> bool {
+        return false;
+    }
+
+    fn is_emissive(&self) -> bool {
+        return false;
+    }
 
     fn emitted(&self, _u: f64, _v: f64, _p: Vec3) -> Vec3 {
         return Vec3::new(0.0, 0.0, 0.0);
     }
+
+    // The surface reflectance at the hit, for the albedo output pass.
+    fn albedo(&self, _hit_record: &HitRecord) -> Vec3 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
 }
\ No newline at end of file
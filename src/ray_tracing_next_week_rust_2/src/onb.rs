@@ -0,0 +1,27 @@
+use crate::vec3::{self, Vec3};
+
+// An orthonormal basis with w along a given direction, for turning directions picked about the z
+// axis into directions about that one.
+#[derive(Copy, Clone, Debug)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3
+}
+
+impl Onb {
+    pub fn new(direction: Vec3) -> Self {
+        let w = direction.normalized();
+        let a = if w.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let v = vec3::cross(&w, &a).normalized();
+        let u = vec3::cross(&w, &v);
+        Self {
+            u, v, w
+        }
+    }
+
+    pub fn transform(&self, local: Vec3) -> Vec3 {
+        // The direction with the given coordinates in the basis.
+        return local.x * self.u + local.y * self.v + local.z * self.w;
+    }
+}
@@ -0,0 +1,150 @@
+use std::f64::consts::PI;
+
+use crate::background::{Background, BackgroundSample, SkySettings};
+use crate::color::WorkingSpace;
+use crate::rtweekend::degrees_to_radians;
+use crate::sampler::Sampler;
+use crate::onb::Onb;
+use crate::vec3::{self, Vec3};
+
+// Half the angle the sun's disk spans, in degrees.
+const SUN_ANGULAR_RADIUS: f64 = 0.265;
+// Luminance of the sun's disk outside the atmosphere, in kcd/m^2.
+const SUN_LUMINANCE: f64 = 1.96e6;
+// The model's luminances are in kcd/m^2; this brings a white diffuse surface lit by a 100 klux
+// sun and sky to about 1.
+const LUMINANCE_SCALE: f64 = PI / 100.0;
+
+// The clear sky of Preetham, Shirley and Smits, "A Practical Analytic Model for Daylight" (1999),
+// with the sun's disk dimmed and reddened by the air it shines through. Turbidity is the haziness
+// of the air, from about 2 for a very clear sky to 10 for a hazy one. Directions below the horizon
+// get the sky's color at the horizon.
+pub struct PhysicalSky {
+    sun_direction: Vec3,
+    cos_sun_radius: f64,
+    sun_color: Vec3,
+    zenith: Vec3,                  // x, y chromaticity and luminance Y straight up
+    coefficients: [[f64; 5]; 3],   // Perez coefficients A to E of x, y and Y
+    sun_denominators: Vec3,        // Perez function of x, y and Y at the zenith
+    space: WorkingSpace,
+    settings: SkySettings          // As given to new
+}
+
+impl PhysicalSky {
+    pub fn new(sun_elevation: f64, sun_azimuth: f64, turbidity: f64, space: WorkingSpace) -> Self {
+        // The sun is sun_elevation degrees above the horizon, and sun_azimuth degrees around the
+        // vertical axis from -z towards +x.
+        let elevation = degrees_to_radians(sun_elevation);
+        let azimuth = degrees_to_radians(sun_azimuth);
+        let sun_direction = Vec3::new(elevation.cos() * azimuth.sin(), elevation.sin(), -elevation.cos() * azimuth.cos());
+        let theta_s = PI / 2.0 - elevation.max(0.0);
+        let t = turbidity;
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let zenith_chromaticity = |m: [[f64; 4]; 3]| {
+            let angles = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+            let weights = [t * t, t, 1.0];
+            let mut value = 0.0;
+            for (row, weight) in m.iter().zip(weights) {
+                value += weight * row.iter().zip(angles).map(|(a, b)| a * b).sum::<f64>();
+            }
+            return value;
+        };
+        let zenith_x = zenith_chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886]
+        ]);
+        let zenith_y = zenith_chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688]
+        ]);
+
+        let coefficients = [
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703]
+        ];
+        let sun_denominators = Vec3::new(
+            perez(&coefficients[0], 0.0, theta_s),
+            perez(&coefficients[1], 0.0, theta_s),
+            perez(&coefficients[2], 0.0, theta_s));
+
+        Self {
+            sun_direction,
+            cos_sun_radius: degrees_to_radians(SUN_ANGULAR_RADIUS).cos(),
+            sun_color: space.rec709_to_working(sun_color(theta_s, t)),
+            zenith: Vec3::new(zenith_x, zenith_y, zenith_luminance),
+            coefficients,
+            sun_denominators,
+            space,
+            settings: SkySettings { sun_elevation, sun_azimuth, turbidity }
+        }
+    }
+
+    fn sky_color(&self, direction: Vec3) -> Vec3 {
+        let theta = direction.y.clamp(0.001, 1.0).acos();
+        let gamma = vec3::dot(&direction, &self.sun_direction).clamp(-1.0, 1.0).acos();
+        let x = self.zenith.x * perez(&self.coefficients[0], theta, gamma) / self.sun_denominators.x;
+        let y = self.zenith.y * perez(&self.coefficients[1], theta, gamma) / self.sun_denominators.y;
+        let luminance = self.zenith.z * perez(&self.coefficients[2], theta, gamma) / self.sun_denominators.z;
+        return LUMINANCE_SCALE * self.space.xyz_to_working(Vec3::new(x / y * luminance, luminance, (1.0 - x - y) / y * luminance));
+    }
+}
+
+impl Background for PhysicalSky {
+    fn color(&self, direction: Vec3) -> Vec3 {
+        let sky = self.sky_color(direction);
+        if vec3::dot(&direction, &self.sun_direction) >= self.cos_sun_radius {
+            return sky + self.sun_color;
+        }
+        return sky;
+    }
+
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<BackgroundSample> {
+        // Picks a direction in the sun's disk, uniformly over its solid angle.
+        let u = sampler.get_2d();
+        let cos_theta = 1.0 - u.0 * (1.0 - self.cos_sun_radius);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u.1;
+
+        let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        let direction = Onb::new(self.sun_direction).transform(local).normalized();
+
+        return Some(BackgroundSample::new(direction, self.color(direction), self.pdf(direction)));
+    }
+
+    fn pdf(&self, direction: Vec3) -> f64 {
+        if vec3::dot(&direction, &self.sun_direction) < self.cos_sun_radius {
+            return 0.0;
+        }
+        return 1.0 / (2.0 * PI * (1.0 - self.cos_sun_radius));
+    }
+
+    fn sky_settings(&self) -> Option<SkySettings> {
+        return Some(self.settings);
+    }
+}
+
+fn perez(coefficients: &[f64; 5], theta: f64, gamma: f64) -> f64 {
+    // The Perez et al. sky distribution at zenith angle theta and angle gamma from the sun.
+    let [a, b, c, d, e] = *coefficients;
+    return (1.0 + a * (b / theta.cos()).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos());
+}
+
+fn sun_color(theta_s: f64, turbidity: f64) -> Vec3 {
+    // The sun's radiance after Rayleigh and aerosol scattering along its path through the
+    // atmosphere, at wavelengths standing in for the sRGB red, green and blue.
+    let relative_air_mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_s.to_degrees()).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let transmittance = |lambda: f64| {
+        // lambda in micrometers.
+        let rayleigh = (-0.008735 * lambda.powf(-4.08) * relative_air_mass).exp();
+        let aerosol = (-beta * lambda.powf(-1.3) * relative_air_mass).exp();
+        return rayleigh * aerosol;
+    };
+    let scale = LUMINANCE_SCALE * SUN_LUMINANCE;
+    return Vec3::new(scale * transmittance(0.680), scale * transmittance(0.550), scale * transmittance(0.440));
+}
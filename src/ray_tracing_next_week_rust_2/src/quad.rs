@@ -1,12 +1,12 @@
-use std::sync::Arc;
-
 use crate::vec3::{Vec3, dot, cross};
 use crate::aabb::AABB;
-use crate::material::Material;
-use crate::hittable::Hittable;
+use crate::hittable::{Hittable, SurfaceSample};
 use crate::ray::Ray;
 use crate::hitrecord::HitRecord;
 use crate::interval::Interval;
+use crate::sampler::Sampler;
+use crate::material::Material;
+use std::sync::Arc;
 
 pub struct Quad{
     q: Vec3,
@@ -35,10 +35,9 @@ impl Quad {
             q, u, v, material, bbox, d, w, normal
         }
     }
-}
 
-impl Hittable for Quad {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn intersect(&self, ray: &Ray, ray_t: &Interval) -> Option<(f64, f64, f64)> {
+        // The ray parameter and plane coordinates of the ray's hit, if it hits the quad.
         let denom = dot(&self.normal, &ray.direction);
 
         if denom.abs() < 1e-8 {
@@ -64,10 +63,67 @@ impl Hittable for Quad {
             return None;
         }
 
-        return Some(HitRecord::new(ray, intersection, self.normal, t, alpha, beta, self.material));
+        return Some((t, alpha, beta));
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, ray_t: &Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
+        let (t, alpha, beta) = self.intersect(ray, ray_t)?;
+        return Some(HitRecord::new(ray, ray.at(t), self.normal, t, alpha, beta, self.material));
     }
 
     fn bounding_box(&self) -> AABB {
         return self.bbox;
     }
+
+    fn is_emissive(&self, mats: &[Arc<dyn Material>]) -> bool {
+        return mats[self.material as usize].is_emissive();
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f64) -> f64 {
+        // Points are picked uniformly over the area, which a distance and slant stretch over
+        // solid angle.
+        let ray = Ray::new2(origin, direction, time);
+        let Some((t, _, _)) = self.intersect(&ray, &Interval::new(0.0001, f64::INFINITY)) else {
+            return 0.0;
+        };
+        let distance_squared = t * t * direction.length_squared();
+        let cosine = (dot(&direction, &self.normal) / direction.length()).abs();
+        let area = cross(&self.u, &self.v).length();
+        return distance_squared / (cosine * area);
+    }
+
+    fn random(&self, origin: Vec3, _time: f64, sampler: &mut dyn Sampler) -> Vec3 {
+        let (a, b) = sampler.get_2d();
+        return self.q + a * self.u + b * self.v - origin;
+    }
+
+    fn sample_surface(&self, _time: f64, sampler: &mut dyn Sampler) -> Option<SurfaceSample> {
+        let (a, b) = sampler.get_2d();
+        return Some(SurfaceSample {
+            p: self.q + a * self.u + b * self.v,
+            normal: self.normal,
+            u: a,
+            v: b,
+            material: self.material,
+            pdf: 1.0 / cross(&self.u, &self.v).length()
+        });
+    }
+
+    fn surface_pdf(&self, p: Vec3, _time: f64) -> f64 {
+        // Points found by intersecting a ray with the quad are only on its plane to within
+        // rounding.
+        if (dot(&self.normal, &p) - self.d).abs() > 1e-6 * self.d.abs().max(1.0) {
+            return 0.0;
+        }
+        let planar_p = p - self.q;
+        let alpha = dot(&self.w, &cross(&planar_p, &self.v));
+        let beta = dot(&self.w, &cross(&self.u, &planar_p));
+        let unit_interval = Interval::new(0.0, 1.0);
+        if !unit_interval.contains(alpha) || !unit_interval.contains(beta) {
+            return 0.0;
+        }
+        return 1.0 / cross(&self.u, &self.v).length();
+    }
 }
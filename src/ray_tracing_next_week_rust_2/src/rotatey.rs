@@ -6,6 +6,7 @@ use crate::ray::Ray;
 use crate::vec3::Vec3;
 use crate::aabb::AABB;
 use crate::rtweekend::degrees_to_radians;
+use crate::sampler::Sampler;
 
 pub struct RotateY {
     sin_theta: f64,
@@ -54,7 +55,7 @@ impl RotateY {
 }
 
 impl Hittable for RotateY {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
         // Translate the ray from world space to object space.
 
         let origin = Vec3::new(
@@ -72,7 +73,7 @@ impl Hittable for RotateY {
         let rotated_r = Ray::new2(origin, direction, ray.tm);
 
         // Determine whether an intersection exists in object space (and if so, where).
-        let hit_result = self.hittable.hit(&rotated_r, ray_t);
+        let hit_result = self.hittable.hit(&rotated_r, ray_t, sampler);
         if let Some(hit) = hit_result {
             // Transform the intersection from object space back to world space.
 
@@ -2,45 +2,105 @@ use crate::vec3::{self, Vec3};
 
 use std::f64::consts::PI;
 
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+// A small, fast SplitMix64 random number generator. Renders are reproducible because every camera
+// sample gets its own generator, seeded from the render seed and the sample's pixel and index,
+// rather than sharing one between samples on a thread.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: mix(seed) }
+    }
+
+    pub fn for_sample(seed: u64, x: u64, y: u64, sample: u64) -> Self {
+        // Hashes the coordinates of the sample into the seed, so nearby samples get unrelated
+        // sequences.
+        Self { state: hash(&[seed, x, y, sample]) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        return mix(self.state);
+    }
+}
+
+pub fn hash(values: &[u64]) -> u64 {
+    // Combines the values into one well mixed 64-bit value.
+    let mut state = 0;
+    for value in values {
+        state = mix(state ^ value.wrapping_add(GOLDEN_GAMMA));
+    }
+    return state;
+}
+
+fn mix(value: u64) -> u64 {
+    // The SplitMix64 output function.
+    let mut z = value;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    return z ^ (z >> 31);
+}
+
 pub fn degrees_to_radians(degrees: f64) -> f64 {
     return degrees * PI / 180.0;
 }
 
-pub fn random_double() -> f64 {
-    // Returns a random double in [0, 1).
-    return rand::random();
+pub fn random_double(rng: &mut Rng) -> f64 {
+    // Returns a random double in [0, 1), from the top 53 bits of the generator's output.
+    return (rng.next_u64() >> 11) as f64 * (1.0 / (1_u64 << 53) as f64);
 }
 
-pub fn random_double_min_max(min: f64, max: f64) -> f64 {
+pub fn random_double_min_max(rng: &mut Rng, min: f64, max: f64) -> f64 {
     // Returns a random real in [min,max).
-    return min + (max - min) * random_double();
+    return min + (max - min) * random_double(rng);
+}
+
+pub fn shuffle<T>(rng: &mut Rng, values: &mut [T]) {
+    // Fisher-Yates shuffle.
+    for i in (1..values.len()).rev() {
+        let j = (random_double(rng) * (i + 1) as f64) as usize;
+        values.swap(i, j.min(i));
+    }
 }
 
-pub fn random_vec3() -> Vec3 {
-    return Vec3::new(random_double(), random_double(), random_double());
+pub fn random_vec3(rng: &mut Rng) -> Vec3 {
+    return Vec3::new(random_double(rng), random_double(rng), random_double(rng));
 }
 
-pub fn random_vec3_min_max(min: f64, max: f64) -> Vec3 {
-    return Vec3::new(random_double_min_max(min, max), random_double_min_max(min, max), random_double_min_max(min, max));
+pub fn random_vec3_min_max(rng: &mut Rng, min: f64, max: f64) -> Vec3 {
+    return Vec3::new(random_double_min_max(rng, min, max), random_double_min_max(rng, min, max), random_double_min_max(rng, min, max));
 }
 
-pub fn random_unit_vector() -> Vec3 {
-    loop {
-        let p = random_vec3_min_max(-1.0, 1.0);
-        let len_sqr = p.length_squared();
-        if 1e-6 < len_sqr && len_sqr <= 1.0 {
-            return p / len_sqr.sqrt();
-        }
-    }
+pub fn sample_unit_vector(u: (f64, f64)) -> Vec3 {
+    // Maps a point of the unit square to the unit sphere, keeping areas in proportion.
+    let z = 1.0 - 2.0 * u.0;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * PI * u.1;
+    return Vec3::new(r * phi.cos(), r * phi.sin(), z);
 }
 
-pub fn random_in_unit_disc() -> Vec3 {
-    loop {
-        let p = Vec3::new(random_double_min_max(-1.0, 1.0), random_double_min_max(-1.0, 1.0), 0.0);
-        if p.length_squared() < 1.0 {
-            return p;
-        }
+pub fn sample_unit_disc(u: (f64, f64)) -> Vec3 {
+    // Maps a point of the unit square to the unit disc with Shirley and Chiu's concentric
+    // mapping, which keeps areas in proportion and nearby points close together.
+    let (a, b) = (2.0 * u.0 - 1.0, 2.0 * u.1 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
     }
+    let (r, theta) = if a.abs() > b.abs() { (a, PI / 4.0 * (b / a)) } else { (b, PI / 2.0 - PI / 4.0 * (a / b)) };
+    return Vec3::new(r * theta.cos(), r * theta.sin(), 0.0);
+}
+
+pub fn sample_cosine_direction(u: (f64, f64)) -> Vec3 {
+    // Maps a point of the unit square to the hemisphere about +z, with density cos(theta) / pi,
+    // by lifting a point of the unit disc onto it.
+    let d = sample_unit_disc(u);
+    let z = (1.0 - d.x * d.x - d.y * d.y).max(0.0).sqrt();
+    return Vec3::new(d.x, d.y, z);
 }
 
 pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
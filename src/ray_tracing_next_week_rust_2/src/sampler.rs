@@ -0,0 +1,536 @@
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+
+use crate::rtweekend::{self, hash, Rng};
+
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0; // Largest double below one
+const MAX_STRATA: u64 = 1 << 20;
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53,
+    59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131
+];
+const MASK_SIZE: u64 = 64; // Width and height of the tiled blue-noise mask
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum SamplerKind {
+    Random,     // Independent uniform random numbers
+    Stratified, // One jittered stratum per sample, in a shuffled order for each dimension
+    Halton,     // The Halton sequence, shifted at random for each pixel
+    Sobol,      // Owen-scrambled Sobol points, scrambled independently for each pixel
+    BlueNoise   // Owen-scrambled Sobol points shared by all pixels, shifted by a blue-noise mask
+}
+
+impl SamplerKind {
+    pub fn create(&self, seed: u64, samples_per_pixel: u64) -> Box<dyn Sampler> {
+        let pixel_sample = PixelSample { seed, x: 0, y: 0, sample: 0, dimension: 0 };
+        return match self {
+            SamplerKind::Random => Box::new(RandomSampler { seed, rng: Rng::new(seed) }),
+            SamplerKind::Stratified => Box::new(StratifiedSampler { pixel_sample, strata: samples_per_pixel.clamp(1, MAX_STRATA) }),
+            SamplerKind::Halton => Box::new(HaltonSampler { pixel_sample }),
+            SamplerKind::Sobol => Box::new(SobolSampler { pixel_sample }),
+            SamplerKind::BlueNoise => Box::new(BlueNoiseSampler { pixel_sample })
+        };
+    }
+}
+
+// The sample values of a pixel sample, in [0, 1). Each call returns the next dimension of the
+// sample; as the camera and materials ask for their dimensions in the same order on every sample,
+// the values a dimension takes over a pixel's samples are spread evenly across the interval.
+pub trait Sampler {
+    // Starts the given sample of pixel x, y at its first dimension.
+    fn start_pixel_sample(&mut self, x: u64, y: u64, sample: u64);
+
+    fn get_1d(&mut self) -> f64;
+
+    fn get_2d(&mut self) -> (f64, f64);
+
+    // A copy of the sampler that carries on from the same dimension.
+    fn clone_box(&self) -> Box<dyn Sampler>;
+}
+
+#[derive(Clone, Debug)]
+struct PixelSample {
+    seed: u64,
+    x: u64,
+    y: u64,
+    sample: u64,
+    dimension: u64
+}
+
+impl PixelSample {
+    fn start(&mut self, x: u64, y: u64, sample: u64) {
+        self.x = x;
+        self.y = y;
+        self.sample = sample;
+        self.dimension = 0;
+    }
+
+    fn next_dimension(&mut self) -> u64 {
+        self.dimension += 1;
+        return self.dimension - 1;
+    }
+
+    fn pixel_hash(&self, dimension: u64) -> u64 {
+        // Differs for every pixel and dimension, but not from one sample to the next.
+        return hash(&[self.seed, self.x, self.y, dimension]);
+    }
+
+    fn sample_hash(&self, dimension: u64, component: u64) -> u64 {
+        return hash(&[self.seed, self.x, self.y, self.sample, dimension, component]);
+    }
+}
+
+#[derive(Clone)]
+struct RandomSampler {
+    seed: u64,
+    rng: Rng
+}
+
+impl Sampler for RandomSampler {
+    fn start_pixel_sample(&mut self, x: u64, y: u64, sample: u64) {
+        self.rng = Rng::for_sample(self.seed, x, y, sample);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        return rtweekend::random_double(&mut self.rng);
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        return (rtweekend::random_double(&mut self.rng), rtweekend::random_double(&mut self.rng));
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        return Box::new(self.clone());
+    }
+}
+
+#[derive(Clone)]
+struct StratifiedSampler {
+    pixel_sample: PixelSample,
+    strata: u64 // Strata of each dimension; the samples per pixel
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: u64, y: u64, sample: u64) {
+        self.pixel_sample.start(x, y, sample);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        // Each dimension visits the strata in its own random order, so that the dimensions of a
+        // sample aren't correlated.
+        let dimension = self.pixel_sample.next_dimension();
+        let index = self.pixel_sample.sample % self.strata;
+        let stratum = permutation_element(index, self.strata, self.pixel_sample.pixel_hash(dimension));
+        let jitter = unit_float(self.pixel_sample.sample_hash(dimension, 0));
+        return (stratum as f64 + jitter) / self.strata as f64;
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        // The square is divided into a grid of at least as many cells as there are strata, and
+        // each sample takes a different cell.
+        let dimension = self.pixel_sample.next_dimension();
+        let columns = (self.strata as f64).sqrt().ceil() as u64;
+        let rows = self.strata.div_ceil(columns);
+        let index = self.pixel_sample.sample % self.strata;
+        let cell = permutation_element(index, columns * rows, self.pixel_sample.pixel_hash(dimension));
+        let jitter_x = unit_float(self.pixel_sample.sample_hash(dimension, 0));
+        let jitter_y = unit_float(self.pixel_sample.sample_hash(dimension, 1));
+        return (((cell % columns) as f64 + jitter_x) / columns as f64, ((cell / columns) as f64 + jitter_y) / rows as f64);
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        return Box::new(self.clone());
+    }
+}
+
+#[derive(Clone)]
+struct HaltonSampler {
+    pixel_sample: PixelSample
+}
+
+impl HaltonSampler {
+    fn sample_dimension(&mut self) -> f64 {
+        // Dimension n is the radical inverse of the sample index in the n-th prime base. The
+        // later dimensions, beyond the table of primes, are uniform random.
+        let dimension = self.pixel_sample.next_dimension();
+        let Some(&base) = PRIMES.get(dimension as usize) else {
+            return unit_float(self.pixel_sample.sample_hash(dimension, 0));
+        };
+        let shift = unit_float(self.pixel_sample.pixel_hash(dimension));
+        return wrap(radical_inverse(base, self.pixel_sample.sample) + shift);
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: u64, y: u64, sample: u64) {
+        self.pixel_sample.start(x, y, sample);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        return self.sample_dimension();
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        return (self.sample_dimension(), self.sample_dimension());
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        return Box::new(self.clone());
+    }
+}
+
+#[derive(Clone)]
+struct SobolSampler {
+    pixel_sample: PixelSample
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: u64, y: u64, sample: u64) {
+        self.pixel_sample.start(x, y, sample);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        return self.get_2d().0;
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        // Every dimension is a scrambling of the first two Sobol dimensions, whose points are
+        // shuffled differently for each pixel and dimension so that the dimensions don't correlate.
+        let dimension = self.pixel_sample.next_dimension();
+        return scrambled_sobol_2d(self.pixel_sample.sample, self.pixel_sample.pixel_hash(dimension));
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        return Box::new(self.clone());
+    }
+}
+
+#[derive(Clone)]
+struct BlueNoiseSampler {
+    pixel_sample: PixelSample
+}
+
+impl Sampler for BlueNoiseSampler {
+    fn start_pixel_sample(&mut self, x: u64, y: u64, sample: u64) {
+        self.pixel_sample.start(x, y, sample);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        return self.get_2d().0;
+    }
+
+    fn get_2d(&mut self) -> (f64, f64) {
+        // All pixels share the points of a dimension, each shifted by the blue-noise mask at the
+        // pixel. Neighbouring pixels then get very different shifts, which leaves their errors as
+        // high frequency noise rather than clumps. The mask is offset for every dimension.
+        let dimension = self.pixel_sample.next_dimension();
+        let scramble = hash(&[self.pixel_sample.seed, dimension]);
+        let (u, v) = scrambled_sobol_2d(self.pixel_sample.sample, scramble);
+        let offset = hash(&[scramble]);
+        let (x, y) = (self.pixel_sample.x + offset % MASK_SIZE, self.pixel_sample.y + (offset >> 32) % MASK_SIZE);
+        return (wrap(u + blue_noise(x, y)), wrap(v + blue_noise(x + MASK_SIZE / 2, y + MASK_SIZE / 2)));
+    }
+
+    fn clone_box(&self) -> Box<dyn Sampler> {
+        return Box::new(self.clone());
+    }
+}
+
+fn unit_float(bits: u64) -> f64 {
+    // A double in [0, 1) from the top 53 bits.
+    return (bits >> 11) as f64 * (1.0 / (1_u64 << 53) as f64);
+}
+
+fn wrap(value: f64) -> f64 {
+    // Wraps a value in [0, 2) around into [0, 1).
+    return if value >= 1.0 { value - 1.0 } else { value };
+}
+
+fn radical_inverse(base: u64, index: u64) -> f64 {
+    // Mirrors the digits of index in the given base about the radix point.
+    let inverse_base = 1.0 / base as f64;
+    let mut index = index;
+    let mut value = 0.0;
+    let mut digit_scale = inverse_base;
+    while index > 0 {
+        value += (index % base) as f64 * digit_scale;
+        digit_scale *= inverse_base;
+        index /= base;
+    }
+    return value.min(ONE_MINUS_EPSILON);
+}
+
+fn permutation_element(index: u64, length: u64, seed: u64) -> u64 {
+    // Element index of a random permutation of 0..length chosen by seed, without building the
+    // permutation (Kensler, "Correlated Multi-Jittered Sampling"). Length is at most 2^32.
+    let length = length as u32;
+    let p = seed as u32;
+    let mut w = length - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    let mut i = index as u32;
+    loop {
+        // A hash that is invertible on the w-bit values, applied until it lands inside the range.
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    return ((i as u64) + (p as u64)) % length as u64;
+}
+
+fn sobol_2d(index: u32) -> (u32, u32) {
+    // The first two dimensions of the Sobol sequence, as 32-bit fractions. The first is the van
+    // der Corput sequence; the second's direction numbers are each the previous one xor itself
+    // shifted right by one.
+    let mut second = 0;
+    let mut direction: u32 = 1 << 31;
+    let mut bits = index;
+    while bits != 0 {
+        if bits & 1 != 0 {
+            second ^= direction;
+        }
+        direction ^= direction >> 1;
+        bits >>= 1;
+    }
+    return (index.reverse_bits(), second);
+}
+
+fn nested_uniform_scramble(value: u32, seed: u32) -> u32 {
+    // Owen scrambling of a 32-bit fraction, using the hash of Burley, "Practical Hash-based Owen
+    // Scrambling": each bit is flipped depending only on the bits above it.
+    let mut x = value.reverse_bits();
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    return x.reverse_bits();
+}
+
+fn scrambled_sobol_2d(index: u64, seed: u64) -> (f64, f64) {
+    // Point index of an Owen-scrambled Sobol sequence. Scrambling the index too shuffles the
+    // order of the points, while keeping any power of two of them starting at zero well spread.
+    let seeds = [seed as u32, (seed >> 32) as u32, hash(&[seed]) as u32];
+    let (u, v) = sobol_2d(nested_uniform_scramble(index as u32, seeds[0]));
+    let to_float = |bits: u32| bits as f64 / (1_u64 << 32) as f64;
+    return (to_float(nested_uniform_scramble(u, seeds[1])), to_float(nested_uniform_scramble(v, seeds[2])));
+}
+
+fn blue_noise(x: u64, y: u64) -> f64 {
+    // The mask tiles the image plane.
+    static MASK: OnceLock<Vec<f64>> = OnceLock::new();
+    let mask = MASK.get_or_init(void_and_cluster);
+    return mask[((y % MASK_SIZE) * MASK_SIZE + x % MASK_SIZE) as usize];
+}
+
+fn void_and_cluster() -> Vec<f64> {
+    // A tileable blue-noise mask built with Ulichney's void-and-cluster method: cells are ranked
+    // by the order they are switched on, each time in the largest gap left between the cells
+    // already on. Gaps and clusters are found from the cells' energy, the sum of a Gaussian of
+    // the wrapped-around distance to every cell that is on.
+    let size = MASK_SIZE as usize;
+    let cells = size * size;
+    let sigma = 1.5;
+    let kernel: Vec<f64> = (0..cells).map(|cell| {
+        let (dx, dy) = ((cell % size).min(size - cell % size), (cell / size).min(size - cell / size));
+        (-((dx * dx + dy * dy) as f64) / (2.0 * sigma * sigma)).exp()
+    }).collect();
+    let update = |energy: &mut [f64], cell: usize, sign: f64| {
+        for (other, value) in energy.iter_mut().enumerate() {
+            let dx = (other % size + size - cell % size) % size;
+            let dy = (other / size + size - cell / size) % size;
+            *value += sign * kernel[dy * size + dx];
+        }
+    };
+    let tightest_cluster = |on: &[bool], energy: &[f64]| (0..cells).filter(|&cell| on[cell])
+        .max_by(|&a, &b| energy[a].total_cmp(&energy[b])).unwrap();
+    let largest_void = |on: &[bool], energy: &[f64]| (0..cells).filter(|&cell| !on[cell])
+        .min_by(|&a, &b| energy[a].total_cmp(&energy[b])).unwrap();
+
+    // Start with a tenth of the cells on, at random, and spread them out by moving the tightest
+    // cluster into the largest void until that is where it came from.
+    let mut on = vec![false; cells];
+    let mut energy = vec![0.0; cells];
+    let initial = cells / 10;
+    let mut rng = Rng::new(0);
+    let mut count = 0;
+    while count < initial {
+        let cell = (rtweekend::random_double(&mut rng) * cells as f64) as usize;
+        if !on[cell] {
+            on[cell] = true;
+            update(&mut energy, cell, 1.0);
+            count += 1;
+        }
+    }
+    for _move in 0..cells {
+        let cluster = tightest_cluster(&on, &energy);
+        on[cluster] = false;
+        update(&mut energy, cluster, -1.0);
+        let void = largest_void(&on, &energy);
+        on[void] = true;
+        update(&mut energy, void, 1.0);
+        if void == cluster {
+            break;
+        }
+    }
+
+    // The initial cells are ranked by taking out the tightest cluster until none are left, and
+    // the others by filling the largest void. Past half full, the largest void among the cells on
+    // is the tightest cluster among those off, as the method asks.
+    let mut ranks = vec![0; cells];
+    let (mut initial_on, mut initial_energy) = (on.clone(), energy.clone());
+    for rank in (0..initial).rev() {
+        let cluster = tightest_cluster(&initial_on, &initial_energy);
+        initial_on[cluster] = false;
+        update(&mut initial_energy, cluster, -1.0);
+        ranks[cluster] = rank;
+    }
+    for rank in initial..cells {
+        let void = largest_void(&on, &energy);
+        on[void] = true;
+        update(&mut energy, void, 1.0);
+        ranks[void] = rank;
+    }
+    return ranks.into_iter().map(|rank| (rank as f64 + 0.5) / cells as f64).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(kind: SamplerKind, seed: u64, samples_per_pixel: u64) -> Vec<f64> {
+        // Every value of a few pixels' samples, asking for 1D and 2D dimensions in turn.
+        let mut sampler = kind.create(seed, samples_per_pixel);
+        let mut values = Vec::new();
+        for (x, y) in [(0, 0), (1, 0), (17, 63), (100, 5)] {
+            for sample in 0..samples_per_pixel {
+                sampler.start_pixel_sample(x, y, sample);
+                for _dimension in 0..20 {
+                    values.push(sampler.get_1d());
+                    let (u, v) = sampler.get_2d();
+                    values.extend([u, v]);
+                }
+            }
+        }
+        return values;
+    }
+
+    #[test]
+    fn samples_are_in_unit_interval() {
+        for kind in SamplerKind::value_variants() {
+            for value in sequence(*kind, 3, 64) {
+                assert!((0.0..1.0).contains(&value), "{:?} gave {}", kind, value);
+            }
+        }
+    }
+
+    #[test]
+    fn seed_fixes_the_sequence() {
+        for kind in SamplerKind::value_variants() {
+            assert_eq!(sequence(*kind, 11, 16), sequence(*kind, 11, 16), "{:?}", kind);
+            assert_ne!(sequence(*kind, 11, 16), sequence(*kind, 12, 16), "{:?}", kind);
+        }
+    }
+
+    fn strata_1d(sampler: &mut dyn Sampler, count: u64, dimension: u64) -> Vec<u64> {
+        // The stratum of count equal intervals each of the pixel's samples lands in.
+        return (0..count).map(|sample| {
+            sampler.start_pixel_sample(5, 9, sample);
+            for _skipped in 0..dimension {
+                sampler.get_1d();
+            }
+            (sampler.get_1d() * count as f64) as u64
+        }).collect();
+    }
+
+    fn is_permutation(mut values: Vec<u64>) -> bool {
+        values.sort();
+        return values.iter().enumerate().all(|(index, value)| index as u64 == *value);
+    }
+
+    #[test]
+    fn stratified_samples_take_separate_strata() {
+        for count in [1, 7, 16, 25] {
+            let mut sampler = SamplerKind::Stratified.create(1, count);
+            for dimension in 0..4 {
+                assert!(is_permutation(strata_1d(sampler.as_mut(), count, dimension)), "{} samples", count);
+            }
+
+            // 2D dimensions use a grid of at least count cells, each taken at most once.
+            let columns = (count as f64).sqrt().ceil() as u64;
+            let rows = count.div_ceil(columns);
+            let mut cells: Vec<u64> = (0..count).map(|sample| {
+                sampler.start_pixel_sample(5, 9, sample);
+                let (u, v) = sampler.get_2d();
+                (v * rows as f64) as u64 * columns + (u * columns as f64) as u64
+            }).collect();
+            cells.sort();
+            cells.dedup();
+            assert_eq!(cells.len() as u64, count);
+        }
+    }
+
+    #[test]
+    fn sobol_samples_take_separate_strata() {
+        // Every power of two of a pixel's samples, from the first, has one point in each
+        // elementary interval of the square of that area.
+        let mut sampler = SamplerKind::Sobol.create(4, 64);
+        for log_count in 0..=6 {
+            let count = 1_u64 << log_count;
+            for dimension in 0..4 {
+                let points: Vec<(f64, f64)> = (0..count).map(|sample| {
+                    sampler.start_pixel_sample(5, 9, sample);
+                    for _skipped in 0..dimension {
+                        sampler.get_2d();
+                    }
+                    sampler.get_2d()
+                }).collect();
+                for bits in 0..=log_count {
+                    let (columns, rows) = (1_u64 << bits, 1_u64 << (log_count - bits));
+                    let cells: Vec<u64> = points.iter()
+                        .map(|(u, v)| (v * rows as f64) as u64 * columns + (u * columns as f64) as u64)
+                        .collect();
+                    assert!(is_permutation(cells), "{} samples, {}x{} intervals", count, columns, rows);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn halton_radical_inverse() {
+        assert_eq!(radical_inverse(2, 0), 0.0);
+        assert_eq!(radical_inverse(2, 1), 0.5);
+        assert_eq!(radical_inverse(2, 6), 0.375);
+        assert!((radical_inverse(3, 5) - 7.0 / 9.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn blue_noise_mask_ranks_every_cell_once() {
+        let mask = void_and_cluster();
+        let cells = MASK_SIZE * MASK_SIZE;
+        let ranks: Vec<u64> = mask.iter().map(|value| (value * cells as f64) as u64).collect();
+        assert!(is_permutation(ranks));
+    }
+}
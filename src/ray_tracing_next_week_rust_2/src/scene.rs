@@ -0,0 +1,21 @@
+use crate::hittable::Hittable;
+use crate::hittables::Hittables;
+use crate::background::Background;
+use crate::color::WorkingSpace;
+
+// What the camera renders: the world, the emissive objects in it that are sampled for direct
+// lighting, and the light from beyond it, with colors in the working space the scene was built in.
+pub struct Scene {
+    pub world: Box<dyn Hittable>,
+    pub lights: Hittables,
+    pub background: Box<dyn Background>,
+    pub working_space: WorkingSpace
+}
+
+impl Scene {
+    pub fn new(world: Box<dyn Hittable>, lights: Hittables, background: Box<dyn Background>, working_space: WorkingSpace) -> Self {
+        Self {
+            world, lights, background, working_space
+        }
+    }
+}
@@ -6,19 +6,82 @@ use crate::material::Material;
 use crate::hittable::Hittable;
 use crate::hittables::Hittables;
 use crate::vec3::Vec3;
-use crate::rtweekend::{self, random_double_min_max};
+use crate::color::WorkingSpace;
+use crate::rtweekend::{self, random_double_min_max, Rng};
 use crate::sphere::Sphere;
 use crate::bvhnode::BvhNode;
 use crate::texture::Texture;
 use crate::textures::{CheckerTexture, SolidColorTexture, NoiseTexture};
 use crate::imagetexture::ImageTexture;
-use crate::camera::Camera;
+use crate::camera::CameraBuilder;
+use crate::background::Background;
+use crate::scene::Scene;
+use crate::backgrounds::SolidBackground;
+use crate::physicalsky::PhysicalSky;
 use crate::quad::Quad;
 use crate::translate::Translate;
 use crate::rotatey::RotateY;
 use crate::constantmedium::ConstantMedium;
 
-pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
+pub struct SceneInfo {
+    pub name: &'static str,
+    pub description: &'static str
+}
+
+// Scenes in the order of the book; the 1-based position is also accepted on the command line.
+pub const SCENES: [SceneInfo; 10] = [
+    SceneInfo { name: "bouncing-spheres", description: "Random moving spheres on a checkered ground" },
+    SceneInfo { name: "checkered-spheres", description: "Two checker textured spheres" },
+    SceneInfo { name: "earth", description: "Image textured globe" },
+    SceneInfo { name: "perlin-spheres", description: "Perlin noise textured spheres" },
+    SceneInfo { name: "quads", description: "Five coloured quads" },
+    SceneInfo { name: "simple-light", description: "Perlin spheres lit by a quad light" },
+    SceneInfo { name: "cornell-box", description: "Cornell box" },
+    SceneInfo { name: "cornell-smoke", description: "Cornell box with smoke filled blocks" },
+    SceneInfo { name: "final", description: "Final scene of the book (needs earthmap.jpg)" },
+    SceneInfo { name: "sunlit-spheres", description: "The bouncing spheres under a daylight sky and sun" }
+];
+
+// Camera, what the camera looks at and the materials indexed by the world's hit records.
+pub type SceneParts = (CameraBuilder, Scene, Vec<Arc<dyn Material>>);
+
+fn lights(world: &[Arc<dyn Hittable>], mats: &[Arc<dyn Material>]) -> Hittables {
+    // The emissive objects at the top level of the world, which are sampled for direct lighting.
+    let lights: Vec<Arc<dyn Hittable>> = world.iter().filter(|object| object.is_emissive(mats)).cloned().collect();
+    return Hittables::new(&lights);
+}
+
+pub fn find_scene(name_or_number: &str) -> Option<&'static SceneInfo> {
+    if let Ok(number) = name_or_number.parse::<usize>() {
+        return number.checked_sub(1).and_then(|index| SCENES.get(index));
+    }
+    return SCENES.iter().find(|scene| scene.name == name_or_number);
+}
+
+pub fn load_scene(scene: &SceneInfo, texture_path: &str, rng: &mut Rng, space: WorkingSpace) -> Result<SceneParts, String> {
+    match scene.name {
+        "bouncing-spheres" => Ok(bouncing_spheres(rng, space)),
+        "checkered-spheres" => Ok(checkered_spheres(space)),
+        "earth" => earth(texture_path, space),
+        "perlin-spheres" => Ok(perlin_spheres(rng, space)),
+        "quads" => Ok(quads(space)),
+        "simple-light" => Ok(simple_light(rng, space)),
+        "cornell-box" => Ok(cornell_box(space)),
+        "cornell-smoke" => Ok(cornell_smoke(space)),
+        "final" => final_scene(texture_path, rng, space),
+        "sunlit-spheres" => Ok(sunlit_spheres(rng, space)),
+        _ => Err(format!("unrecognised scene '{}'", scene.name))
+    }
+}
+
+pub fn sunlit_spheres(rng: &mut Rng, space: WorkingSpace) -> SceneParts {
+    let (camera, mut scene, mats) = bouncing_spheres(rng, space);
+    scene.background = Box::new(PhysicalSky::new(35.0, 120.0, 3.0, space));
+
+    return (camera, scene, mats);
+}
+
+pub fn final_scene(texture_path: &str, rng: &mut Rng, space: WorkingSpace) -> Result<SceneParts, String> {
     let aspect_ratio = 1.0;
     let image_width = 300;
     let vfov = 40.0;
@@ -26,13 +89,21 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dy
     let look_at = Vec3::new(278.0, 278.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.0, 0.0, 0.0), space));
 
     let mut mats: Vec<Arc<dyn Material>> = Vec::new();
 
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
 
-    let ground: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.48, 0.83, 0.53)));
+    let ground: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.48, 0.83, 0.53), space));
     let ground_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&ground)));
     mats.push(ground_material); // 0
 
@@ -46,7 +117,7 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dy
             let z0 = -1000.0 + (j as f64 * w);
             let y0 = 0.0;
             let x1 = x0 + w;
-            let y1 = random_double_min_max(1.0, 101.0);
+            let y1 = random_double_min_max(rng, 1.0, 101.0);
             let z1 = z0 + w;
 
             let the_box: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(x0, y0, z0), Vec3::new(x1, y1, z1), 0));
@@ -59,7 +130,7 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dy
 
     world.push(boxes_hittable);
 
-    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(7.0, 7.0, 7.0)));
+    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(7.0, 7.0, 7.0), space));
     let light_mat: Arc<dyn Material> = Arc::new(DiffuseLight::new(Arc::clone(&light_tex)));
     mats.push(light_mat); // 1
 
@@ -69,7 +140,7 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dy
     let center1 = Vec3::new(400.0, 400.0, 200.0);
     let center2 = center1 + Vec3::new(30.0, 0.0, 0.0);
 
-    let sphere_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.7, 0.3, 0.1)));
+    let sphere_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.7, 0.3, 0.1), space));
     let sphere_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&sphere_tex)));
     mats.push(sphere_material); // 2
     let sphere_1: Arc<dyn Hittable> = Arc::new(Sphere::new_moving(center1, center2, 50.0, 2));
@@ -80,19 +151,19 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dy
     let sphere_2: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(260.0, 150.0, 45.0), 50.0, 3));
     world.push(sphere_2);
 
-    let sphere_material3: Arc<dyn Material> = Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.9),1.0));
+    let sphere_material3: Arc<dyn Material> = Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.9),1.0, space));
     mats.push(sphere_material3); // 4
     let sphere_3: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 150.0, 145.0), 50.0, 4));
     world.push(sphere_3);
 
     let boundary: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(360.0, 150.0, 145.0), 70.0, 3));
-    let tex_1: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.4, 0.9)));
+    let tex_1: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.4, 0.9), space));
     let phase_material_1: Arc<dyn Material + Sync> = Arc::new(Isotropic::new(tex_1));
     mats.push(phase_material_1); // 5
     let constant_medium: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(Arc::clone(&boundary), 5, 0.2));
     world.push(boundary);
     world.push(constant_medium);
-    let tex_2: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0, 1.0, 1.0)));
+    let tex_2: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0, 1.0, 1.0), space));
     let phase_material_2: Arc<dyn Material + Sync> = Arc::new(Isotropic::new(tex_2));
     mats.push(phase_material_2); // 6
     let boundary_2: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 5000.0, 3));
@@ -102,20 +173,20 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dy
 
     let earth_texture_path = Path::new(texture_path).join("earthmap.jpg");
 
-    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(earth_texture_path.to_str().unwrap()));
+    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(&earth_texture_path.to_string_lossy(), space)?);
     let earth_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&earth_texture)));
     mats.push(earth_material); // 7
     let globe : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(400.0, 200.0, 400.0), 100.0, 7));
     world.push(globe);
 
-    let per_tex: Arc<dyn Texture> = Arc::new(NoiseTexture::new(0.2));
+    let per_tex: Arc<dyn Texture> = Arc::new(NoiseTexture::new(0.2, rng));
     let per_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&per_tex)));
     mats.push(per_material); // 8
     let sphere_per: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(220.0, 280.0, 300.0), 80.0, 8));
     world.push(sphere_per);
 
     let mut spheres: Vec<Arc<dyn Hittable>> = Vec::new();
-    let spheres_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73)));
+    let spheres_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73), space));
     let spheres_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&spheres_tex)));
     mats.push(spheres_material); // 9
     for i in 0..10 {
@@ -136,10 +207,12 @@ pub fn final_scene(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dy
     let hittables = Hittables::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable, mats);
+    let scene = Scene::new(world_hittable, lights(&world, &mats), background, space);
+
+    return Ok((camera, scene, mats));
 }
 
-pub fn cornell_smoke() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
+pub fn cornell_smoke(space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 40.0;
@@ -147,53 +220,65 @@ pub fn cornell_smoke() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
     let look_at = Vec3::new(278.0, 278.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.0, 0.0, 0.0), space));
 
     let mut mats: Vec<Arc<dyn Material>> = Vec::new();
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
-/*
-    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.65, 0.05, 0.05)));
-    let red_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&red)));
-    let white: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73)));
-    let white_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&white)));
-    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.12, 0.45, 0.15)));
-    let green_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&green)));
-
-    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(7.0, 7.0, 7.0)));
-    let light_mat: Arc<dyn Material> = Arc::new(DiffuseLight::new(Arc::clone(&light_tex)));
 
-    let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), Arc::clone(&green_material)));
+    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.65, 0.05, 0.05), space));
+    let red_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&red))));
+    let white: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73), space));
+    let white_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&white))));
+    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.12, 0.45, 0.15), space));
+    let green_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&green))));
+
+    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(7.0, 7.0, 7.0), space));
+    let light_mat = add_material(&mut mats, Arc::new(DiffuseLight::new(Arc::clone(&light_tex))));
+    let black_phase = add_material(&mut mats, Arc::new(Isotropic::new(Arc::new(SolidColorTexture::new(Vec3::new(0.0, 0.0, 0.0), space)))));
+    let white_phase = add_material(&mut mats, Arc::new(Isotropic::new(Arc::new(SolidColorTexture::new(Vec3::new(1.0, 1.0, 1.0), space)))));
+
+    let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), green_material));
     world.push(quad1);
-    let quad2: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), Arc::clone(&red_material)));
+    let quad2: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), red_material));
     world.push(quad2);
-    let quad3: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(113.0, 554.0, 127.0), Vec3::new(333.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 305.0), Arc::clone(&light_mat)));
+    let quad3: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(113.0, 554.0, 127.0), Vec3::new(333.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 305.0), light_mat));
     world.push(quad3);
-    let quad4: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 555.0), Arc::clone(&white_material)));
+    let quad4: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 555.0), white_material));
     world.push(quad4);
-    let quad5: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 555.0, 555.0), Vec3::new(-555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -555.0), Arc::clone(&white_material)));
+    let quad5: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 555.0, 555.0), Vec3::new(-555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -555.0), white_material));
     world.push(quad5);
-    let quad6: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 555.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Arc::clone(&white_material)));
+    let quad6: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 555.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), white_material));
     world.push(quad6);
 
-    let box1: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 330.0, 165.0), Arc::clone(&white_material)));
+    let box1: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 330.0, 165.0), white_material));
     let box1: Arc<dyn Hittable> = Arc::new(RotateY::new(15.0, box1));
     let box1: Arc<dyn Hittable> = Arc::new(Translate::new(Vec3::new(265.0, 0.0, 295.0), box1));
-    let box1: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(box1, Arc::new(SolidColorTexture::new(Vec3::new(0.0, 0.0, 0.0))), 0.01));
+    let box1: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(box1, black_phase, 0.01));
     world.push(box1);
-    let box2: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 165.0, 165.0), Arc::clone(&white_material)));
+    let box2: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 165.0, 165.0), white_material));
     let box2: Arc<dyn Hittable> = Arc::new(RotateY::new(-18.0, box2));
     let box2: Arc<dyn Hittable> = Arc::new(Translate::new(Vec3::new(130.0, 0.0, 65.0), box2));
-    let box2: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(box2, Arc::new(SolidColorTexture::new(Vec3::new(1.0, 1.0, 1.0))), 0.01));
+    let box2: Arc<dyn Hittable> = Arc::new(ConstantMedium::new(box2, white_phase, 0.01));
     world.push(box2);
-*/
+
     // let hittables = Hittables::new(&world[..]);
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
- 
-    return (camera, world_hittable, mats);
+
+    let scene = Scene::new(world_hittable, lights(&world, &mats), background, space);
+
+    return (camera, scene, mats);
 }
 
-pub fn cornell_box() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
+pub fn cornell_box(space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 40.0;
@@ -201,51 +286,61 @@ pub fn cornell_box() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
     let look_at = Vec3::new(278.0, 278.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.0, 0.0, 0.0), space));
 
     let mut mats: Vec<Arc<dyn Material>> = Vec::new();
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
-/*
-    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.65, 0.05, 0.05)));
-    let red_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&red)));
-    let white: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73)));
-    let white_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&white)));
-    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.12, 0.45, 0.15)));
-    let green_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&green)));
-
-    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(15.0, 15.0, 15.0)));
-    let light_mat: Arc<dyn Material> = Arc::new(DiffuseLight::new(Arc::clone(&light_tex)));
 
-    let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), Arc::clone(&green_material)));
+    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.65, 0.05, 0.05), space));
+    let red_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&red))));
+    let white: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.73, 0.73, 0.73), space));
+    let white_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&white))));
+    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.12, 0.45, 0.15), space));
+    let green_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&green))));
+
+    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(15.0, 15.0, 15.0), space));
+    let light_mat = add_material(&mut mats, Arc::new(DiffuseLight::new(Arc::clone(&light_tex))));
+
+    let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), green_material));
     world.push(quad1);
-    let quad2: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), Arc::clone(&red_material)));
+    let quad2: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Vec3::new(0.0, 0.0, 555.0), red_material));
     world.push(quad2);
-    let quad3: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(343.0, 554.0, 332.0), Vec3::new(-130.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -105.0), Arc::clone(&light_mat)));
+    let quad3: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(343.0, 554.0, 332.0), Vec3::new(-130.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -105.0), light_mat));
     world.push(quad3);
-    let quad4: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 555.0), Arc::clone(&white_material)));
+    let quad4: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 555.0), white_material));
     world.push(quad4);
-    let quad5: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 555.0, 555.0), Vec3::new(-555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -555.0), Arc::clone(&white_material)));
+    let quad5: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(555.0, 555.0, 555.0), Vec3::new(-555.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -555.0), white_material));
     world.push(quad5);
-    let quad6: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 555.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), Arc::clone(&white_material)));
+    let quad6: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(0.0, 0.0, 555.0), Vec3::new(555.0, 0.0, 0.0), Vec3::new(0.0, 555.0, 0.0), white_material));
     world.push(quad6);
 
-    let box1: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 330.0, 165.0), Arc::clone(&white_material)));
+    let box1: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 330.0, 165.0), white_material));
     let box1: Arc<dyn Hittable> = Arc::new(RotateY::new(15.0, box1));
     let box1: Arc<dyn Hittable> = Arc::new(Translate::new(Vec3::new(265.0, 0.0, 295.0), box1));
     world.push(box1);
-    let box2: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 165.0, 165.0), Arc::clone(&white_material)));
+    let box2: Arc<dyn Hittable> = Arc::new(box3(Vec3::new(0.0, 0.0, 0.0), Vec3::new(165.0, 165.0, 165.0), white_material));
     let box2: Arc<dyn Hittable> = Arc::new(RotateY::new(-18.0, box2));
     let box2: Arc<dyn Hittable> = Arc::new(Translate::new(Vec3::new(130.0, 0.0, 65.0), box2));
     world.push(box2);
- */
+
     // let hittables = Hittables::new(&world[..]);
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable, mats);
+    let scene = Scene::new(world_hittable, lights(&world, &mats), background, space);
+
+    return (camera, scene, mats);
 }
 
-pub fn simple_light() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
+pub fn simple_light(rng: &mut Rng, space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -253,31 +348,41 @@ pub fn simple_light() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
     let look_at = Vec3::new(0.0, 2.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.0, 0.0, 0.0), space));
 
     let mut mats: Vec<Arc<dyn Material>> = Vec::new();
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
-/*
-    let per_tex: Arc<dyn Texture> = Arc::new(NoiseTexture::new(4.0));
-    let per_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&per_tex)));
-    let sphere_1: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, Arc::clone(&per_material)));
-    let sphere_2: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 2.0, 0.0), 2.0, Arc::clone(&per_material)));
+
+    let per_tex: Arc<dyn Texture> = Arc::new(NoiseTexture::new(4.0, rng));
+    let per_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&per_tex))));
+    let sphere_1: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, per_material));
+    let sphere_2: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 2.0, 0.0), 2.0, per_material));
     world.push(sphere_1);
     world.push(sphere_2);
 
-    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(4.0, 4.0, 4.0)));
-    let light_mat: Arc<dyn Material> = Arc::new(DiffuseLight::new(Arc::clone(&light_tex)));
-    let quad: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(3.0, 1.0, -2.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), Arc::clone(&light_mat)));
+    let light_tex: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(4.0, 4.0, 4.0), space));
+    let light_mat = add_material(&mut mats, Arc::new(DiffuseLight::new(Arc::clone(&light_tex))));
+    let quad: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(3.0, 1.0, -2.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0), light_mat));
     world.push(quad);
- */
+
     // let hittables = Hittables::new(&world[..]);
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable, mats);
+    let scene = Scene::new(world_hittable, lights(&world, &mats), background, space);
+
+    return (camera, scene, mats);
 }
 
-pub fn quads() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
+pub fn quads(space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 80.0;
@@ -285,42 +390,52 @@ pub fn quads() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
     let mut mats: Vec<Arc<dyn Material>> = Vec::new();
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
-/*
-    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0, 0.2, 0.2)));
-    let left_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&red)));
-    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 1.0, 0.2)));
-    let back_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&green)));
-    let blue: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.2, 1.0)));
-    let right_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&blue)));
-    let orange: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0,0.5, 0.0)));
-    let upper_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&orange)));
-    let teal: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2,0.8, 0.8)));
-    let lower_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&teal)));
-
-    let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(-3.0, -2.0, 5.0), Vec3::new(0.0, 0.0, -4.0), Vec3::new(0.0, 4.0, 0.0), Arc::clone(&left_material)));
-    let quad2: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(-2.0, -2.0, 0.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 4.0, 0.0), Arc::clone(&back_material)));
-    let quad3: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(3.0, -2.0, 1.0), Vec3::new(0.0, 0.0, 4.0), Vec3::new(0.0, 4.0, 0.0), Arc::clone(&right_material)));
-    let quad4: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(-2.0, 3.0, 1.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 4.0), Arc::clone(&upper_material)));
-    let quad5: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(-2.0, -3.0, 5.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -4.0), Arc::clone(&lower_material)));
+
+    let red: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0, 0.2, 0.2), space));
+    let left_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&red))));
+    let green: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 1.0, 0.2), space));
+    let back_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&green))));
+    let blue: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.2, 1.0), space));
+    let right_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&blue))));
+    let orange: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(1.0,0.5, 0.0), space));
+    let upper_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&orange))));
+    let teal: Arc<dyn Texture> = Arc::new(SolidColorTexture::new(Vec3::new(0.2,0.8, 0.8), space));
+    let lower_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&teal))));
+
+    let quad1: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(-3.0, -2.0, 5.0), Vec3::new(0.0, 0.0, -4.0), Vec3::new(0.0, 4.0, 0.0), left_material));
+    let quad2: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(-2.0, -2.0, 0.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 4.0, 0.0), back_material));
+    let quad3: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(3.0, -2.0, 1.0), Vec3::new(0.0, 0.0, 4.0), Vec3::new(0.0, 4.0, 0.0), right_material));
+    let quad4: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(-2.0, 3.0, 1.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 4.0), upper_material));
+    let quad5: Arc<dyn Hittable> = Arc::new(Quad::new(Vec3::new(-2.0, -3.0, 5.0), Vec3::new(4.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -4.0), lower_material));
 
     world.push(quad1);
     world.push(quad2);
     world.push(quad3);
     world.push(quad4);
     world.push(quad5);
- */
+
     // let hittables = Hittables::new(&world[..]);
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable, mats);
+    let scene = Scene::new(world_hittable, lights(&world, &mats), background, space);
+
+    return (camera, scene, mats);
 }
 
-pub fn perlin_spheres() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
+pub fn perlin_spheres(rng: &mut Rng, space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -328,26 +443,36 @@ pub fn perlin_spheres() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
     let mut mats: Vec<Arc<dyn Material>> = Vec::new();
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
-/*
-    let perlin_texture: Arc<dyn Texture> = Arc::new(NoiseTexture::new(1.0));
-    let perlin_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&perlin_texture)));
 
-    let sphere_1 : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, Arc::clone(&perlin_material)));
+    let perlin_texture: Arc<dyn Texture> = Arc::new(NoiseTexture::new(1.0, rng));
+    let perlin_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&perlin_texture))));
+
+    let sphere_1 : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, perlin_material));
     world.push(sphere_1);
-    let sphere_2 : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 2.0, 0.0), 2.0, Arc::clone(&perlin_material)));
+    let sphere_2 : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 2.0, 0.0), 2.0, perlin_material));
     world.push(sphere_2);
- */
+
     let hittables = Hittables::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable, mats);
+    let scene = Scene::new(world_hittable, lights(&world, &mats), background, space);
+
+    return (camera, scene, mats);
 }
 
-pub fn earth(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
+pub fn earth(texture_path: &str, space: WorkingSpace) -> Result<SceneParts, String> {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -355,26 +480,36 @@ pub fn earth(texture_path: &str) -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Mate
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
     let mut mats: Vec<Arc<dyn Material>> = Vec::new();
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
-/*
+
     let earth_texture_path = Path::new(texture_path).join("earthmap.jpg");
-    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(earth_texture_path.to_str().unwrap()));
-    let earth_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&earth_texture)));
+    let earth_texture: Arc<dyn Texture> = Arc::new(ImageTexture::new(&earth_texture_path.to_string_lossy(), space)?);
+    let earth_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&earth_texture))));
 
-    let globe : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 2.0, Arc::clone(&earth_material)));
+    let globe : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 2.0, earth_material));
     world.push(globe);
- */
+
     // let hittables = Hittables::new(&world[..]);
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable, mats);
+    let scene = Scene::new(world_hittable, lights(&world, &mats), background, space);
+
+    return Ok((camera, scene, mats));
 }
 
-pub fn checkered_spheres() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
+pub fn checkered_spheres(space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -384,30 +519,40 @@ pub fn checkered_spheres() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.0;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
     let mut mats: Vec<Arc<dyn Material>> = Vec::new();
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
-/*
-    let even = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.3, 0.1)));
-    let odd = Arc::new(SolidColorTexture::new(Vec3::new(0.9, 0.9, 0.9)));
+
+    let even = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.3, 0.1), space));
+    let odd = Arc::new(SolidColorTexture::new(Vec3::new(0.9, 0.9, 0.9), space));
     let checker: Arc<dyn Texture> = Arc::new(CheckerTexture::new(0.32, even, odd));
-    let checker_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&checker)));
+    let checker_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&checker))));
 
-    let checker_sphere_1 : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -2.0, 0.0), 2.0, Arc::clone(&checker_material)));
+    let checker_sphere_1 : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -2.0, 0.0), 2.0, checker_material));
     world.push(checker_sphere_1);
 
-    let checker_sphere_2 : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 2.0, 0.0), 2.0, Arc::clone(&checker_material)));
+    let checker_sphere_2 : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 2.0, 0.0), 2.0, checker_material));
     world.push(checker_sphere_2);
- */
+
     // let hittables = Hittables::new(&world[..]);
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable, mats);
+    let scene = Scene::new(world_hittable, lights(&world, &mats), background, space);
+
+    return (camera, scene, mats);
 }
 
-pub fn bouncing_spheres() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>) {
+pub fn bouncing_spheres(rng: &mut Rng, space: WorkingSpace) -> SceneParts {
     let aspect_ratio = 16.0 / 9.0;
     let image_width = 400;
     let vfov = 20.0;
@@ -415,79 +560,93 @@ pub fn bouncing_spheres() -> (Camera, Box<dyn Hittable>, Vec<Arc<dyn Material>>)
     let look_at = Vec3::new(0.0, 0.0, 0.0);
     let v_up = Vec3::new(0.0, 1.0, 0.0);
     let defocus_angle = 0.6;
-    let camera = Camera::new(aspect_ratio, image_width, vfov, look_from, look_at, v_up, defocus_angle);
+    let focus_dist = 10.0;
+    let camera = CameraBuilder::new()
+        .aspect_ratio(aspect_ratio)
+        .image_width(image_width)
+        .vfov(vfov)
+        .look_from(look_from)
+        .look_at(look_at)
+        .v_up(v_up)
+        .defocus_angle(defocus_angle)
+        .focus_distance(focus_dist);
+    let background: Box<dyn Background> = Box::new(SolidBackground::new(Vec3::new(0.70, 0.80, 1.00), space));
 
-    let mut materials: Vec<Arc<dyn Material>> = Vec::new();
+    let mut mats: Vec<Arc<dyn Material>> = Vec::new();
 
-    let material_ground: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(Vec3::new(0.5, 0.5, 0.5)))));
-    let material_1: Arc<dyn Material> = Arc::new(Dielectric::new(1.5));
-    let material_2: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(Vec3::new(0.4, 0.2, 0.1)))));
-    let material_3: Arc<dyn Material> = Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0));
+    let material_ground = add_material(&mut mats, Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(Vec3::new(0.5, 0.5, 0.5), space)))));
+    let material_1 = add_material(&mut mats, Arc::new(Dielectric::new(1.5)));
+    let material_2 = add_material(&mut mats, Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(Vec3::new(0.4, 0.2, 0.1), space)))));
+    let material_3 = add_material(&mut mats, Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0, space)));
 
-    let mut mats: Vec<Arc<dyn Material>> = Vec::new();
     let mut world: Vec<Arc<dyn Hittable>> = Vec::new();
-/*
-    let even = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.3, 0.1)));
-    let odd = Arc::new(SolidColorTexture::new(Vec3::new(0.9, 0.9, 0.9)));
+
+    let even = Arc::new(SolidColorTexture::new(Vec3::new(0.2, 0.3, 0.1), space));
+    let odd = Arc::new(SolidColorTexture::new(Vec3::new(0.9, 0.9, 0.9), space));
     let checker: Arc<dyn Texture> = Arc::new(CheckerTexture::new(0.32, even, odd));
-    let checker_material: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::clone(&checker)));
-    let checker_sphere : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, Arc::clone(&checker_material)));
+    let checker_material = add_material(&mut mats, Arc::new(Lambertian::new(Arc::clone(&checker))));
+    let checker_sphere : Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, checker_material));
     world.push(checker_sphere);
 
-    let hittable: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, Arc::clone(&material_ground)));
+    let hittable: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, material_ground));
     world.push(hittable);
 
-    let hittable1: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 1.0, 0.0), 1.0, Arc::clone(&material_1)));
+    let hittable1: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(0.0, 1.0, 0.0), 1.0, material_1));
     world.push(hittable1);
 
-    let hittable2: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(-4.0, 1.0, 0.0), 1.0, Arc::clone(&material_2)));
+    let hittable2: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(-4.0, 1.0, 0.0), 1.0, material_2));
     world.push(hittable2);
 
-    let hittable3: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(4.0, 1.0, 0.0), 1.0, Arc::clone(&material_3)));
+    let hittable3: Arc<dyn Hittable> = Arc::new(Sphere::new(Vec3::new(4.0, 1.0, 0.0), 1.0, material_3));
     world.push(hittable3);
 
     for a in -11..11 {
         for b in -11..11 {
-            let choose_mat = rtweekend::random_double();
-            let center = Vec3::new((a as f64) + 0.9 * rtweekend::random_double(), 0.2, (b as f64) + 0.9 * rtweekend::random_double());
+            let choose_mat = rtweekend::random_double(rng);
+            let center = Vec3::new((a as f64) + 0.9 * rtweekend::random_double(rng), 0.2, (b as f64) + 0.9 * rtweekend::random_double(rng));
 
             if (center - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
                 if choose_mat < 0.8 {
                     // diffuse
-                    let albedo = rtweekend::random_vec3() * rtweekend::random_vec3();
-                    let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(albedo))));
-                    materials.push(Arc::clone(&mat));
+                    let albedo = rtweekend::random_vec3(rng) * rtweekend::random_vec3(rng);
+                    let mat = add_material(&mut mats, Arc::new(Lambertian::new(Arc::new(SolidColorTexture::new(albedo, space)))));
 
-                    let center_2 = center + Vec3::new(0.0, random_double_min_max(0.0, 0.5), 0.0);
-                    let sphere = Sphere::new_moving(center, center_2, 0.2, Arc::clone(&mat));
+                    let center_2 = center + Vec3::new(0.0, random_double_min_max(rng, 0.0, 0.5), 0.0);
+                    let sphere = Sphere::new_moving(center, center_2, 0.2, mat);
                     let hittable: Arc<dyn Hittable> = Arc::new(sphere);
                     world.push(hittable);
                 } else if choose_mat < 0.95 {
                     // metal
-                    let albedo = rtweekend::random_vec3_min_max(0.5, 1.0);
-                    let fuzz = rtweekend::random_double_min_max(0.0, 0.5);
-                    let mat: Arc<dyn Material> = Arc::new(Metal::new(albedo, fuzz));
-                    materials.push(Arc::clone(&mat));
+                    let albedo = rtweekend::random_vec3_min_max(rng, 0.5, 1.0);
+                    let fuzz = rtweekend::random_double_min_max(rng, 0.0, 0.5);
+                    let mat = add_material(&mut mats, Arc::new(Metal::new(albedo, fuzz, space)));
 
-                    let hittable: Arc<dyn Hittable> = Arc::new(Sphere::new(center, 0.2, Arc::clone(&mat)));
+                    let hittable: Arc<dyn Hittable> = Arc::new(Sphere::new(center, 0.2, mat));
                     world.push(hittable);
                 } else {
                     // glass
-                    let mat: Arc<dyn Material> = Arc::new(Dielectric::new(1.5));
-                    materials.push(Arc::clone(&mat));
+                    let mat = add_material(&mut mats, Arc::new(Dielectric::new(1.5)));
 
-                    let hittable: Arc<dyn Hittable> = Arc::new(Sphere::new(center, 0.2, Arc::clone(&mat)));
+                    let hittable: Arc<dyn Hittable> = Arc::new(Sphere::new(center, 0.2, mat));
                     world.push(hittable);
                 }
             }
         }
     }
- */
+
     // let hittables = Hittables::new(&world[..]);
     let hittables = BvhNode::new(&world[..]);
     let world_hittable: Box<dyn Hittable> = Box::new(hittables);
 
-    return (camera, world_hittable, mats);
+    let scene = Scene::new(world_hittable, lights(&world, &mats), background, space);
+
+    return (camera, scene, mats);
+}
+
+fn add_material(mats: &mut Vec<Arc<dyn Material>>, material: Arc<dyn Material>) -> i16 {
+    // Appends the material, returning the index to reference it by from hittables.
+    mats.push(material);
+    return (mats.len() - 1) as i16;
 }
 
 fn box3(a: Vec3, b: Vec3, mat: i16) -> Hittables {
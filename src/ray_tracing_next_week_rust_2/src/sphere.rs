@@ -1,13 +1,16 @@
 use crate::vec3::Vec3;
 use crate::vec3;
 use crate::ray::Ray;
-use crate::hittable::Hittable;
+use crate::hittable::{Hittable, SurfaceSample};
 use crate::hitrecord::HitRecord;
 use crate::interval::Interval;
-use crate::material::Material;
 use crate::aabb::AABB;
-use std::f64;
+use crate::sampler::Sampler;
+use crate::material::Material;
+use crate::onb::Onb;
+use crate::rtweekend::sample_unit_vector;
 use std::sync::Arc;
+use std::f64;
 
 pub struct Sphere {
     center: Ray,
@@ -43,7 +46,7 @@ impl Sphere {
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval, _sampler: &mut dyn Sampler) -> Option<HitRecord> {
         let current_center = self.center.at(ray.tm);
         let oc = current_center - ray.origin;
         let a = ray.direction.length_squared();
@@ -78,6 +81,59 @@ impl Hittable for Sphere {
     fn bounding_box(&self) -> AABB {
         return self.bbox;
     }
+
+    fn is_emissive(&self, mats: &[Arc<dyn Material>]) -> bool {
+        return mats[self.material as usize].is_emissive();
+    }
+
+    fn pdf_value(&self, origin: Vec3, direction: Vec3, time: f64) -> f64 {
+        // random picks directions uniformly in the cone the sphere fills as seen from origin;
+        // there is none from inside it.
+        let to_center = self.center.at(time) - origin;
+        let distance_squared = to_center.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 0.0;
+        }
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        if vec3::dot(&direction.normalized(), &to_center.normalized()) < cos_theta_max {
+            return 0.0;
+        }
+        return 1.0 / (2.0 * f64::consts::PI * (1.0 - cos_theta_max));
+    }
+
+    fn random(&self, origin: Vec3, time: f64, sampler: &mut dyn Sampler) -> Vec3 {
+        let to_center = self.center.at(time) - origin;
+        let distance_squared = to_center.length_squared();
+        let cos_theta_max = (1.0 - self.radius * self.radius / distance_squared).max(0.0).sqrt();
+        let (u1, u2) = sampler.get_2d();
+        let cos_theta = 1.0 + u1 * (cos_theta_max - 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * f64::consts::PI * u2;
+
+        let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+        return Onb::new(to_center).transform(local);
+    }
+
+    fn sample_surface(&self, time: f64, sampler: &mut dyn Sampler) -> Option<SurfaceSample> {
+        let normal = sample_unit_vector(sampler.get_2d());
+        let (u, v) = get_sphere_uv(normal);
+        return Some(SurfaceSample {
+            p: self.center.at(time) + self.radius * normal,
+            normal,
+            u,
+            v,
+            material: self.material,
+            pdf: 1.0 / (4.0 * f64::consts::PI * self.radius * self.radius)
+        });
+    }
+
+    fn surface_pdf(&self, p: Vec3, time: f64) -> f64 {
+        let distance = (p - self.center.at(time)).length();
+        if (distance - self.radius).abs() > 1e-6 * self.radius.max(1.0) {
+            return 0.0;
+        }
+        return 1.0 / (4.0 * f64::consts::PI * self.radius * self.radius);
+    }
 }
 
 fn get_sphere_uv(p: Vec3) -> (f64, f64) {
@@ -3,14 +3,17 @@ use std::sync::Arc;
 use crate::texture::Texture;
 use crate::vec3::Vec3;
 use crate::perlin::Perlin;
+use crate::color::WorkingSpace;
+use crate::rtweekend::Rng;
 
 pub struct SolidColorTexture {
     albedo: Vec3
 }
 
 impl SolidColorTexture {
-    pub fn new(albedo: Vec3) -> Self {
-        Self { albedo }
+    pub fn new(albedo: Vec3, space: WorkingSpace) -> Self {
+        // The albedo is given with sRGB primaries, and kept in the scene's working space.
+        Self { albedo: space.rec709_to_working(albedo) }
     }
 }
 
@@ -38,7 +41,7 @@ impl Texture for CheckerTexture {
         let u_integer = (self.inv_scale * u).floor() as u64;
         let v_integer = (self.inv_scale * v).floor() as u64;
 
-        let is_even = (u_integer + v_integer) % 2 == 0;
+        let is_even = (u_integer + v_integer).is_multiple_of(2);
         if is_even {
             return self.even.color_value(u, v, p);
         }
@@ -52,8 +55,8 @@ pub struct NoiseTexture {
 }
 
 impl NoiseTexture {
-    pub fn new(scale: f64) -> Self {
-        let noise = Perlin::new();
+    pub fn new(scale: f64, rng: &mut Rng) -> Self {
+        let noise = Perlin::new(rng);
         return Self {
             noise, scale
         }
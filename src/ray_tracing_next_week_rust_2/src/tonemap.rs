@@ -0,0 +1,119 @@
+use clap::ValueEnum;
+
+use crate::vec3::Vec3;
+use crate::color::{self, Matrix3};
+
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum ToneMapOperator {
+    Clamp,            // No compression, values above 1 are clipped on output
+    Reinhard,         // L / (1 + L) on luminance
+    ReinhardExtended, // Reinhard, reaching white at the white point
+    Aces,             // Stephen Hill's fit of the ACES reference and sRGB output transforms
+    Hable             // John Hable's filmic curve from Uncharted 2
+}
+
+impl ToneMapOperator {
+    pub fn default_white_point(&self) -> f64 {
+        match self {
+            ToneMapOperator::Hable => 11.2,
+            _ => 4.0
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ToneMapper {
+    operator: ToneMapOperator,
+    exposure_scale: f64, // Linear scale for the exposure given in stops (EV)
+    white_point: f64     // Scene value mapped to white by the extended Reinhard and Hable operators
+}
+
+impl ToneMapper {
+    pub fn new(operator: ToneMapOperator, exposure: f64, white_point: f64) -> Self {
+        Self {
+            operator,
+            exposure_scale: 2.0_f64.powf(exposure),
+            white_point: white_point.max(1e-6)
+        }
+    }
+
+    pub fn map(&self, color: Vec3) -> Vec3 {
+        // Maps exposed linear scene radiance, with sRGB primaries, to linear display values in
+        // [0, 1].
+        let color = self.exposure_scale * color;
+        match self.operator {
+            ToneMapOperator::Clamp => color,
+            ToneMapOperator::Reinhard => scale_luminance(color, |l| l / (1.0 + l)),
+            ToneMapOperator::ReinhardExtended => {
+                let white_squared = self.white_point * self.white_point;
+                scale_luminance(color, |l| l * (1.0 + l / white_squared) / (1.0 + l))
+            }
+            ToneMapOperator::Aces => aces_fitted(color),
+            ToneMapOperator::Hable => {
+                // The curve is applied with the exposure bias of the original presentation.
+                let exposure_bias = 2.0;
+                let white_scale = 1.0 / hable_partial(self.white_point);
+                Vec3::new(
+                    hable_partial(exposure_bias * color.x) * white_scale,
+                    hable_partial(exposure_bias * color.y) * white_scale,
+                    hable_partial(exposure_bias * color.z) * white_scale
+                )
+            }
+        }
+    }
+}
+
+impl Default for ToneMapper {
+    fn default() -> Self {
+        let operator = ToneMapOperator::Clamp;
+        Self::new(operator, 0.0, operator.default_white_point())
+    }
+}
+
+pub fn luminance(color: Vec3) -> f64 {
+    // Rec. 709 luminance weights.
+    return 0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z;
+}
+
+fn scale_luminance(color: Vec3, curve: impl Fn(f64) -> f64) -> Vec3 {
+    // Compresses the luminance, scaling the channels to keep the chromaticity.
+    let l = luminance(color);
+    if l <= 0.0 {
+        return Vec3::new(0.0, 0.0, 0.0);
+    }
+    return (curve(l) / l) * color;
+}
+
+fn aces_fitted(color: Vec3) -> Vec3 {
+    // sRGB => XYZ => D65_2_D60 => AP1 => RRT_SAT
+    let input: Matrix3 = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777]
+    ];
+    // ODT_SAT => XYZ => D60_2_D65 => sRGB
+    let output: Matrix3 = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602]
+    ];
+
+    let v = color::transform(&input, color);
+    let rrt_and_odt_fit = |x: f64| {
+        let a = x * (x + 0.0245786) - 0.000090537;
+        let b = x * (0.983729 * x + 0.4329510) + 0.238081;
+        a / b
+    };
+    let v = Vec3::new(rrt_and_odt_fit(v.x), rrt_and_odt_fit(v.y), rrt_and_odt_fit(v.z));
+    return color::transform(&output, v);
+}
+
+fn hable_partial(x: f64) -> f64 {
+    let a = 0.15; // Shoulder strength
+    let b = 0.50; // Linear strength
+    let c = 0.10; // Linear angle
+    let d = 0.20; // Toe strength
+    let e = 0.02; // Toe numerator
+    let f = 0.30; // Toe denominator
+    return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
+}
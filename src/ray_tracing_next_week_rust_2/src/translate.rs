@@ -5,6 +5,7 @@ use crate::interval::Interval;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 use crate::aabb::AABB;
+use crate::sampler::Sampler;
 
 pub struct Translate {
     offset: Vec3,
@@ -22,12 +23,12 @@ impl Translate {
 }
 
 impl Hittable for Translate {
-    fn hit(&self, ray: &Ray, ray_t: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, ray_t: &Interval, sampler: &mut dyn Sampler) -> Option<HitRecord> {
         // Move the ray backwards by the offset
         let offset_r = Ray::new2(ray.origin - self.offset, ray.direction, ray.tm);
 
         // Determine whether an intersection exists along the offset ray (and if so, where)
-        let hit_result = self.hittable.hit(&offset_r, ray_t);
+        let hit_result = self.hittable.hit(&offset_r, ray_t, sampler);
         if let Some(hit) = hit_result {
             // Move the intersection point forwards by the offset
             let p = hit.p + self.offset;
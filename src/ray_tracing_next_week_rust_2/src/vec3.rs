@@ -7,7 +7,7 @@ use std::f64;
 
 use std::fmt;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vec3 {
     pub x: f64,
     pub y: f64,
@@ -47,6 +47,10 @@ impl Vec3 {
         }
         return self.z;
     }
+
+    pub fn max_component(&self) -> f64 {
+        return self.x.max(self.y).max(self.z);
+    }
 }
 
 impl Add for Vec3 {